@@ -1,4 +1,4 @@
-use bevy::prelude::Res;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 
@@ -32,6 +32,20 @@ impl Debug for GameplayTag {
     }
 }
 
+//序列化为完整标签名，便于在数据表中直接书写标签
+impl Serialize for GameplayTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.get_tag_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for GameplayTag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tag_name = String::deserialize(deserializer)?;
+        Ok(GameplayTag::new(&tag_name))
+    }
+}
+
 impl GameplayTag {
     pub fn new(full_name: &str) -> GameplayTag {
         GameplayTag {
@@ -51,7 +65,7 @@ impl GameplayTag {
     ///
     /// # Arguments
     /// * `tag_to_check` - A reference to the `GameplayTag` that needs to be checked against the current object's tags.
-    /// * `tags_manager` - A resource reference to the `GameplayTagsManager`, which is used to manage and query gameplay tags.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager`, which is used to manage and query gameplay tags.
     ///
     /// # Returns
     /// * `bool` - Returns `true` if the current object contains the `tag_to_check`, otherwise returns `false`.
@@ -71,7 +85,7 @@ impl GameplayTag {
     pub fn matches_tag(
         &self,
         tag_to_check: &GameplayTag,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
     ) -> bool {
         let complete_container = tags_manager.get_single_tag_container(self);
         if let Some(exist_container) = complete_container {
@@ -81,6 +95,22 @@ impl GameplayTag {
         }
     }
 
+    ///
+    /// Checks by name alone if `tag_to_check` is the current tag or one of its parents, i.e. if the
+    /// current tag name equals `tag_to_check` or starts with it followed by a `.`. Unlike
+    /// `matches_tag` this also works for tags that are not registered in the `GameplayTagsManager`.
+    ///
+    pub fn matches_tag_name(&self, tag_to_check: &GameplayTag) -> bool {
+        if !tag_to_check.is_valid() {
+            return false;
+        }
+        let tag_name: &str = &self.tag_name;
+        let parent_name: &str = &tag_to_check.tag_name;
+        tag_name
+            .strip_prefix(parent_name)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    }
+
     /// Checks if the current tag exactly matches the provided `GameplayTag`.
     ///
     /// # Arguments
@@ -114,7 +144,7 @@ impl GameplayTag {
     /// # Arguments
     ///
     /// * `container_to_check` - A reference to a `GameplayTagContainer` whose tags are checked against the current object's tags.
-    /// * `tags_manager` - A resource reference to the `GameplayTagsManager`, used for resolving the full tag container of the current object.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager`, used for resolving the full tag container of the current object.
     ///
     /// # Returns
     ///
@@ -132,7 +162,7 @@ impl GameplayTag {
     pub fn matches_any(
         &self,
         container_to_check: &GameplayTagContainer,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
     ) -> bool {
         let complete_container = tags_manager.get_single_tag_container(self);
         if let Some(exist_container) = complete_container {
@@ -0,0 +1,312 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::{
+    component::Component,
+    entity::{Entity, EntityHashSet},
+    event::EntityEvent,
+    lifecycle::{Insert, Remove, Replace},
+    observer::On,
+    query::{Changed, Has, Or, With},
+    relationship::{Relationship, RelationshipTarget},
+    resource::Resource,
+    system::{Commands, Query, Res, ResMut},
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_container::GameplayTagContainer,
+    gameplay_tag_count_container::{GameplayTagCountContainer, GameplayTagCountNotification},
+    gameplay_tag_inheritance::{
+        GameplayTagSources, build_tag_view, collect_entity_explicit_tags, diff_explicit_tags,
+    },
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+///
+/// Read-only union of an entity's own explicit tags and the explicit tags of every entity related
+/// to it through the relationship `R`.
+///
+/// For example, with an `EquippedBy(player)` relationship on item entities, a
+/// `RelatedGameplayTags<EquippedBy>` on the player answers "does the player effectively have
+/// `Item.Type.Sword`" without walking the equipment. Register `R` with
+/// `GameplayTagsAppExt::add_related_gameplay_tags`; the plugin then keeps the union up to date when
+/// tags or relations change, and triggers `RelatedGameplayTagsChanged<R>` when it changes.
+///
+#[derive(Component, Debug)]
+pub struct RelatedGameplayTags<R: Relationship> {
+    combined_tags: GameplayTagContainer,
+    _marker: PhantomData<R>,
+}
+
+impl<R: Relationship> Default for RelatedGameplayTags<R> {
+    fn default() -> Self {
+        Self {
+            combined_tags: GameplayTagContainer::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<R: Relationship> RelatedGameplayTags<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the owner's explicit tags together with those of all related entities.
+    pub fn get_combined_tags(&self) -> &GameplayTagContainer {
+        &self.combined_tags
+    }
+
+    /// Returns `true` if the owner or one of the related entities has `tag`.
+    pub fn has_tag(&self, tag: &GameplayTag) -> bool {
+        self.combined_tags.has_tag(tag)
+    }
+}
+
+///
+/// Triggered on an entity when the combined tags of its `RelatedGameplayTags<R>` changed.
+/// `added` and `removed` only list explicit tags.
+///
+#[derive(EntityEvent, Debug)]
+pub struct RelatedGameplayTagsChanged<R: Relationship> {
+    pub entity: Entity,
+    pub added: Vec<GameplayTag>,
+    pub removed: Vec<GameplayTag>,
+    _marker: PhantomData<R>,
+}
+
+//需要重新计算汇总标签的拥有者实体
+#[derive(Resource, Debug)]
+pub(crate) struct DirtyRelatedGameplayTags<R: Relationship> {
+    entities: EntityHashSet,
+    _marker: PhantomData<R>,
+}
+
+impl<R: Relationship> Default for DirtyRelatedGameplayTags<R> {
+    fn default() -> Self {
+        Self {
+            entities: EntityHashSet::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<R: Relationship> DirtyRelatedGameplayTags<R> {
+    //实体本身是拥有者，或者通过R关联到某个拥有者
+    fn mark(&mut self, entity: Entity, relation: Option<&R>, is_owner: bool) {
+        if is_owner {
+            self.entities.insert(entity);
+        }
+        if let Some(relation) = relation {
+            self.entities.insert(relation.get());
+        }
+    }
+}
+
+type RelatedTagEntities<'w, 's, R> =
+    Query<'w, 's, (Option<&'static R>, Has<RelatedGameplayTags<R>>)>;
+
+pub(crate) fn mark_related_tags_dirty_on_count_changed<R: Relationship>(
+    trigger: On<GameplayTagCountNotification>,
+    entities: RelatedTagEntities<R>,
+    mut dirty: ResMut<DirtyRelatedGameplayTags<R>>,
+) {
+    let notification = trigger.event();
+    if !notification.has_presence_changes() {
+        return;
+    }
+    if let Ok((relation, is_owner)) = entities.get(notification.entity) {
+        dirty.mark(notification.entity, relation, is_owner);
+    }
+}
+
+pub(crate) fn mark_related_tags_dirty_on_container_removed<R: Relationship>(
+    trigger: On<Remove, (GameplayTagContainer, GameplayTagCountContainer)>,
+    entities: RelatedTagEntities<R>,
+    mut dirty: ResMut<DirtyRelatedGameplayTags<R>>,
+) {
+    let entity = trigger.event().entity;
+    if let Ok((relation, is_owner)) = entities.get(entity) {
+        dirty.mark(entity, relation, is_owner);
+    }
+}
+
+pub(crate) fn mark_related_tags_dirty_on_relation_replaced<R: Relationship>(
+    trigger: On<Replace, R>,
+    relations: Query<&R>,
+    mut dirty: ResMut<DirtyRelatedGameplayTags<R>>,
+) {
+    //关系被移除或指向新的拥有者时都会触发，此时关系组件还是旧值，可以取到之前关联的拥有者
+    if let Ok(relation) = relations.get(trigger.event().entity) {
+        dirty.entities.insert(relation.get());
+    }
+}
+
+pub(crate) fn mark_related_tags_dirty_on_view_inserted<R: Relationship>(
+    trigger: On<Insert, RelatedGameplayTags<R>>,
+    mut dirty: ResMut<DirtyRelatedGameplayTags<R>>,
+) {
+    dirty.entities.insert(trigger.event().entity);
+}
+
+type ChangedRelatedSources<'w, 's, R> = Query<'w, 's, &'static R, Changed<GameplayTagContainer>>;
+type ChangedRelatedOwners<'w, 's, R> = Query<
+    'w,
+    's,
+    Entity,
+    (
+        With<RelatedGameplayTags<R>>,
+        Or<(
+            Changed<GameplayTagContainer>,
+            Changed<<R as Relationship>::RelationshipTarget>,
+        )>,
+    ),
+>;
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_related_gameplay_tags<R: Relationship>(
+    changed_sources: ChangedRelatedSources<R>,
+    changed_owners: ChangedRelatedOwners<R>,
+    mut dirty: ResMut<DirtyRelatedGameplayTags<R>>,
+    targets: Query<&<R as Relationship>::RelationshipTarget>,
+    tag_sources: GameplayTagSources,
+    mut views: Query<&mut RelatedGameplayTags<R>>,
+    tags_manager: Res<GameplayTagsManager>,
+    mut commands: Commands,
+) {
+    let mut dirty_entities = std::mem::take(&mut dirty.entities);
+    dirty_entities.extend(changed_sources.iter().map(|relation| relation.get()));
+    dirty_entities.extend(changed_owners.iter());
+    if dirty_entities.is_empty() {
+        return;
+    }
+
+    let mut dirty_entities: Vec<Entity> = dirty_entities.into_iter().collect();
+    dirty_entities.sort();
+    for entity in dirty_entities {
+        let Ok(view) = views.get(entity) else {
+            continue;
+        };
+        let mut combined = Vec::new();
+        collect_entity_explicit_tags(entity, &tag_sources, &mut combined);
+        if let Ok(target) = targets.get(entity) {
+            for related_entity in target.iter() {
+                collect_entity_explicit_tags(related_entity, &tag_sources, &mut combined);
+            }
+        }
+
+        let combined_tags = build_tag_view(combined, &tags_manager);
+        let added = diff_explicit_tags(&view.combined_tags, &combined_tags);
+        let removed = diff_explicit_tags(&combined_tags, &view.combined_tags);
+        if added.is_empty() && removed.is_empty() {
+            continue;
+        }
+        if let Ok(mut view) = views.get_mut(entity) {
+            view.combined_tags = combined_tags;
+        }
+        commands.trigger(RelatedGameplayTagsChanged::<R> {
+            entity,
+            added,
+            removed,
+            _marker: PhantomData,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{
+        schedule::Schedule,
+        world::{DeferredWorld, World},
+    };
+
+    use super::*;
+    use crate::gameplay_tag_commands::GameplayTagWorldExt;
+
+    #[derive(Component, Debug)]
+    #[relationship(relationship_target = Equipment)]
+    struct EquippedBy(Entity);
+
+    #[derive(Component, Debug)]
+    #[relationship_target(relationship = EquippedBy)]
+    struct Equipment(Vec<Entity>);
+
+    #[derive(Resource, Default)]
+    struct ViewChanges(Vec<(Entity, Vec<GameplayTag>, Vec<GameplayTag>)>);
+
+    fn setup_world() -> (World, Schedule) {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<DirtyRelatedGameplayTags<EquippedBy>>();
+        world.init_resource::<ViewChanges>();
+        world.add_observer(mark_related_tags_dirty_on_count_changed::<EquippedBy>);
+        world.add_observer(mark_related_tags_dirty_on_container_removed::<EquippedBy>);
+        world.add_observer(mark_related_tags_dirty_on_relation_replaced::<EquippedBy>);
+        world.add_observer(mark_related_tags_dirty_on_view_inserted::<EquippedBy>);
+        world.add_observer(
+            |trigger: On<RelatedGameplayTagsChanged<EquippedBy>>, mut world: DeferredWorld| {
+                let event = trigger.event();
+                world.resource_mut::<ViewChanges>().0.push((
+                    event.entity,
+                    event.added.clone(),
+                    event.removed.clone(),
+                ));
+            },
+        );
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_related_gameplay_tags::<EquippedBy>);
+        (world, schedule)
+    }
+
+    fn spawn_with_tag(world: &mut World, tag_name: &str) -> Entity {
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        world.modify_gameplay_tags(entity, |tx| {
+            tx.update_tag_count(&GameplayTag::new(tag_name), 1)
+        });
+        entity
+    }
+
+    fn has_tag(world: &World, entity: Entity, tag_name: &str) -> bool {
+        world
+            .get::<RelatedGameplayTags<EquippedBy>>(entity)
+            .unwrap()
+            .has_tag(&GameplayTag::new(tag_name))
+    }
+
+    #[test]
+    fn owner_view_follows_related_entities_and_their_tags() {
+        let (mut world, mut schedule) = setup_world();
+        let first_owner = spawn_with_tag(&mut world, "D.C");
+        let second_owner = world.spawn_empty().id();
+        world
+            .entity_mut(first_owner)
+            .insert(RelatedGameplayTags::<EquippedBy>::new());
+        world
+            .entity_mut(second_owner)
+            .insert(RelatedGameplayTags::<EquippedBy>::new());
+        let sword = spawn_with_tag(&mut world, "A.B.C");
+        world.entity_mut(sword).insert(EquippedBy(first_owner));
+        schedule.run(&mut world);
+        assert!(has_tag(&world, first_owner, "A.B.C"));
+        assert!(has_tag(&world, first_owner, "D.C"));
+
+        world.modify_gameplay_tags(sword, |tx| {
+            tx.update_tag_count(&GameplayTag::new("Buff.Strength"), 1)
+        });
+        schedule.run(&mut world);
+        assert!(has_tag(&world, first_owner, "Buff.Strength"));
+
+        world.entity_mut(sword).insert(EquippedBy(second_owner));
+        world.resource_mut::<ViewChanges>().0.clear();
+        schedule.run(&mut world);
+        assert!(!has_tag(&world, first_owner, "A.B.C"));
+        assert!(has_tag(&world, first_owner, "D.C"));
+        assert!(has_tag(&world, second_owner, "A.B.C"));
+        assert_eq!(world.resource::<ViewChanges>().0.len(), 2);
+
+        world.entity_mut(sword).remove::<EquippedBy>();
+        schedule.run(&mut world);
+        assert!(!has_tag(&world, second_owner, "A.B.C"));
+        assert!(!has_tag(&world, second_owner, "Buff.Strength"));
+    }
+}
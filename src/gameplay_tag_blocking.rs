@@ -0,0 +1,18 @@
+use bevy::ecs::{entity::Entity, event::EntityEvent};
+
+use crate::gameplay_tag::GameplayTag;
+
+///
+/// Triggered on an entity when adding `tag` to its `GameplayTagCountContainer` was refused because
+/// `blocked_by` is present.
+///
+/// Block rules are declared in the tag table under the blocker row's `blocks` key, or from code with
+/// `GameplayTagsManager::add_tag_block`.
+///
+#[derive(EntityEvent, Debug, Clone)]
+pub struct GameplayTagBlocked {
+    pub entity: Entity,
+    pub tag: GameplayTag,
+    pub blocked_by: GameplayTag,
+    pub requested_delta: i32,
+}
@@ -0,0 +1,394 @@
+use std::panic::AssertUnwindSafe;
+
+use bevy::ecs::{
+    change_detection::DetectChangesMut,
+    entity::Entity,
+    system::{Commands, EntityCommands},
+    world::{CommandQueue, DeferredWorld, EntityWorldMut, World},
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_count_container::{GameplayTagCountContainer, GameplayTagTransaction},
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum GameplayTagCountOp {
+    Update(i32),
+    Set(i32),
+}
+
+impl GameplayTagCountOp {
+    //只有会增加计数的操作才需要补上缺失的标签容器
+    pub(crate) fn adds_tags(&self) -> bool {
+        match self {
+            GameplayTagCountOp::Update(count_delta) => *count_delta > 0,
+            GameplayTagCountOp::Set(new_count) => *new_count > 0,
+        }
+    }
+}
+
+///
+/// Tag operations on a single entity, without passing the container, the `GameplayTagsManager`,
+/// `Commands` and the entity around by hand.
+///
+/// Adding tags inserts a `GameplayTagCountContainer` if the entity has none. Each operation runs
+/// through `GameplayTagWorldExt::modify_gameplay_tags`, so its events are triggered immediately and
+/// in a fixed order. On `EntityCommands` the operation runs when the commands are applied; on
+/// `EntityWorldMut` it runs right away.
+///
+/// # Examples
+/// ```ignore
+/// commands
+///     .entity(player)
+///     .add_gameplay_tag(GameplayTag::new("Status.Burning"))
+///     .set_gameplay_tag_count(GameplayTag::new("Buff.Strength"), 3);
+/// world.entity_mut(player).remove_gameplay_tag(GameplayTag::new("Status.Burning"));
+/// ```
+///
+pub trait GameplayTagEntityExt {
+    /// Adds one stack of `tag`.
+    fn add_gameplay_tag(&mut self, tag: GameplayTag) -> &mut Self;
+
+    /// Removes one stack of `tag`.
+    fn remove_gameplay_tag(&mut self, tag: GameplayTag) -> &mut Self;
+
+    /// Adds `count_delta` stacks of `tag`, or removes them if it is negative.
+    fn update_gameplay_tag_count(&mut self, tag: GameplayTag, count_delta: i32) -> &mut Self;
+
+    /// Sets the explicit count of `tag` to `new_count`.
+    fn set_gameplay_tag_count(&mut self, tag: GameplayTag, new_count: i32) -> &mut Self;
+}
+
+impl GameplayTagEntityExt for EntityCommands<'_> {
+    fn add_gameplay_tag(&mut self, tag: GameplayTag) -> &mut Self {
+        self.update_gameplay_tag_count(tag, 1)
+    }
+
+    fn remove_gameplay_tag(&mut self, tag: GameplayTag) -> &mut Self {
+        self.update_gameplay_tag_count(tag, -1)
+    }
+
+    fn update_gameplay_tag_count(&mut self, tag: GameplayTag, count_delta: i32) -> &mut Self {
+        self.queue(move |mut entity: EntityWorldMut| {
+            apply_gameplay_tag_count_op(&mut entity, tag, GameplayTagCountOp::Update(count_delta));
+        })
+    }
+
+    fn set_gameplay_tag_count(&mut self, tag: GameplayTag, new_count: i32) -> &mut Self {
+        self.queue(move |mut entity: EntityWorldMut| {
+            apply_gameplay_tag_count_op(&mut entity, tag, GameplayTagCountOp::Set(new_count));
+        })
+    }
+}
+
+impl GameplayTagEntityExt for EntityWorldMut<'_> {
+    fn add_gameplay_tag(&mut self, tag: GameplayTag) -> &mut Self {
+        self.update_gameplay_tag_count(tag, 1)
+    }
+
+    fn remove_gameplay_tag(&mut self, tag: GameplayTag) -> &mut Self {
+        self.update_gameplay_tag_count(tag, -1)
+    }
+
+    fn update_gameplay_tag_count(&mut self, tag: GameplayTag, count_delta: i32) -> &mut Self {
+        apply_gameplay_tag_count_op(self, tag, GameplayTagCountOp::Update(count_delta));
+        self
+    }
+
+    fn set_gameplay_tag_count(&mut self, tag: GameplayTag, new_count: i32) -> &mut Self {
+        apply_gameplay_tag_count_op(self, tag, GameplayTagCountOp::Set(new_count));
+        self
+    }
+}
+
+///
+/// Immediate tag updates through `World` access.
+///
+/// On a `World` the resulting events are triggered before the call returns. On a `DeferredWorld`,
+/// as held by hooks and observers, the tag counts change right away as well, but the events are
+/// queued on the world's commands and triggered once the hook or observer has returned.
+///
+pub trait GameplayTagWorldExt {
+    ///
+    /// Runs `modify` as a transaction on the `GameplayTagCountContainer` of `entity` and triggers
+    /// the resulting events right away, before this call returns, instead of when commands are
+    /// next applied. Commands queued by the observers are applied as well.
+    ///
+    /// The events of the transaction only carry net changes and are triggered in a fixed order:
+    /// removals before additions, child tags before their parents. This order only holds within a
+    /// single transaction; separate calls trigger their events in call order.
+    ///
+    /// If `modify` panics, the container stays on the entity with the changes made so far, no
+    /// events are triggered and the panic is resumed.
+    ///
+    /// # Returns
+    /// * `None` if `entity` has no `GameplayTagCountContainer`, otherwise the result of `modify`.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// fn exclusive_system(world: &mut World) {
+    ///     world.modify_gameplay_tags(player, |tx| {
+    ///         tx.remove_tag(&GameplayTag::new("Status.Wet"), 1);
+    ///         tx.add_tag(&GameplayTag::new("Status.Frozen"), 1);
+    ///     });
+    ///     // observers of the removal and the addition have already run here
+    /// }
+    /// ```
+    ///
+    fn modify_gameplay_tags<R>(
+        &mut self,
+        entity: Entity,
+        modify: impl FnOnce(&mut GameplayTagTransaction) -> R,
+    ) -> Option<R>;
+}
+
+impl GameplayTagWorldExt for World {
+    fn modify_gameplay_tags<R>(
+        &mut self,
+        entity: Entity,
+        modify: impl FnOnce(&mut GameplayTagTransaction) -> R,
+    ) -> Option<R> {
+        //先取出容器，这样修改期间可以同时以只读方式借用World来构造Commands
+        let mut count_container = std::mem::take(
+            &mut *self
+                .get_mut::<GameplayTagCountContainer>(entity)?
+                .bypass_change_detection(),
+        );
+        let mut queue = CommandQueue::default();
+        let result = run_transaction(self, &mut count_container, entity, &mut queue, modify);
+        if let Some(mut slot) = self.get_mut::<GameplayTagCountContainer>(entity) {
+            *slot = count_container;
+        }
+        let result = result.unwrap_or_else(|payload| std::panic::resume_unwind(payload));
+        queue.apply(self);
+        Some(result)
+    }
+}
+
+impl GameplayTagWorldExt for DeferredWorld<'_> {
+    fn modify_gameplay_tags<R>(
+        &mut self,
+        entity: Entity,
+        modify: impl FnOnce(&mut GameplayTagTransaction) -> R,
+    ) -> Option<R> {
+        let mut count_container = std::mem::take(
+            &mut *self
+                .get_mut::<GameplayTagCountContainer>(entity)?
+                .bypass_change_detection(),
+        );
+        let mut queue = CommandQueue::default();
+        let result = run_transaction(self, &mut count_container, entity, &mut queue, modify);
+        if let Some(mut slot) = self.get_mut::<GameplayTagCountContainer>(entity) {
+            *slot = count_container;
+        }
+        let result = result.unwrap_or_else(|payload| std::panic::resume_unwind(payload));
+        //DeferredWorld不能直接执行命令，按顺序追加到World的命令队列中
+        self.commands().append(&mut queue);
+        Some(result)
+    }
+}
+
+//捕获修改闭包的panic，让调用方先把取出的容器放回实体，再继续panic
+fn run_transaction<R>(
+    world: &World,
+    count_container: &mut GameplayTagCountContainer,
+    entity: Entity,
+    queue: &mut CommandQueue,
+    modify: impl FnOnce(&mut GameplayTagTransaction) -> R,
+) -> std::thread::Result<R> {
+    std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let tags_manager = world.resource::<GameplayTagsManager>();
+        let mut commands = Commands::new(queue, world);
+        count_container.modify(tags_manager, &mut commands, entity, modify)
+    }))
+}
+
+fn apply_gameplay_tag_count_op(
+    entity: &mut EntityWorldMut,
+    tag: GameplayTag,
+    op: GameplayTagCountOp,
+) {
+    if !entity.contains::<GameplayTagCountContainer>() {
+        if !op.adds_tags() {
+            return;
+        }
+        entity.insert(GameplayTagCountContainer::new());
+    }
+    let entity_id = entity.id();
+    entity.world_scope(|world| {
+        world.modify_gameplay_tags(entity_id, |tx| match op {
+            GameplayTagCountOp::Update(count_delta) => tx.update_tag_count(&tag, count_delta),
+            GameplayTagCountOp::Set(new_count) => tx.set_tag_count(&tag, new_count),
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{
+        event::EntityEvent,
+        observer::On,
+        resource::Resource,
+        world::{DeferredWorld, World},
+    };
+
+    use super::*;
+    use crate::gameplay_tag_count_container::{
+        GameplayTagEventType, OnGameplayEffectTagCountChanged,
+    };
+
+    #[derive(Resource, Default)]
+    struct NewOrRemovedEvents(Vec<(String, bool)>);
+
+    #[derive(EntityEvent)]
+    struct SwapTags {
+        entity: Entity,
+    }
+
+    fn setup_world() -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<NewOrRemovedEvents>();
+        world.add_observer(
+            |trigger: On<OnGameplayEffectTagCountChanged>, mut world: DeferredWorld| {
+                let event = trigger.event();
+                if event.event_type == GameplayTagEventType::NewOrRemoved {
+                    world
+                        .resource_mut::<NewOrRemovedEvents>()
+                        .0
+                        .push((event.tag.get_tag_name().to_string(), event.new_count > 0));
+                }
+            },
+        );
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        world.modify_gameplay_tags(entity, |tx| {
+            tx.add_tag(&GameplayTag::new("A.B.C"), 1);
+        });
+        world.resource_mut::<NewOrRemovedEvents>().0.clear();
+        (world, entity)
+    }
+
+    fn swap_tags(tx: &mut GameplayTagTransaction) {
+        tx.add_tag(&GameplayTag::new("D.C.B"), 1);
+        tx.remove_tag(&GameplayTag::new("A.B.C"), 1);
+    }
+
+    fn expected_events() -> Vec<(String, bool)> {
+        [
+            ("A.B.C", false),
+            ("A.B", false),
+            ("A", false),
+            ("D.C.B", true),
+            ("D.C", true),
+            ("D", true),
+        ]
+        .into_iter()
+        .map(|(tag_name, added)| (tag_name.to_string(), added))
+        .collect()
+    }
+
+    #[test]
+    fn world_transaction_orders_removals_first_and_children_before_parents() {
+        let (mut world, entity) = setup_world();
+        world.modify_gameplay_tags(entity, swap_tags);
+        assert_eq!(world.resource::<NewOrRemovedEvents>().0, expected_events());
+    }
+
+    #[test]
+    fn deferred_world_transaction_triggers_events_after_the_observer() {
+        let (mut world, entity) = setup_world();
+        world.add_observer(|trigger: On<SwapTags>, mut world: DeferredWorld| {
+            let entity = trigger.event().entity;
+            world.modify_gameplay_tags(entity, swap_tags);
+            //计数立即生效，事件在观察者返回后才触发
+            let count_container = world.get::<GameplayTagCountContainer>(entity).unwrap();
+            assert_eq!(count_container.get_tag_count(&GameplayTag::new("D")), 1);
+            assert!(world.resource::<NewOrRemovedEvents>().0.is_empty());
+        });
+        world.trigger(SwapTags { entity });
+        world.flush();
+        assert_eq!(world.resource::<NewOrRemovedEvents>().0, expected_events());
+    }
+
+    #[test]
+    fn panicking_transaction_keeps_container_and_triggers_nothing() {
+        let (mut world, entity) = setup_world();
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            world.modify_gameplay_tags(entity, |tx| {
+                tx.add_tag(&GameplayTag::new("D.C.B"), 1);
+                panic!("修改标签时出错");
+            });
+        }));
+        assert!(result.is_err());
+        assert!(world.resource::<NewOrRemovedEvents>().0.is_empty());
+
+        let count_container = world.get::<GameplayTagCountContainer>(entity).unwrap();
+        assert!(!count_container.is_in_batch());
+        assert_eq!(count_container.get_tag_count(&GameplayTag::new("A.B.C")), 1);
+
+        world.modify_gameplay_tags(entity, |tx| {
+            tx.remove_tag(&GameplayTag::new("A.B.C"), 1);
+        });
+        assert_eq!(world.resource::<NewOrRemovedEvents>().0.len(), 3);
+    }
+
+    fn tag_count(world: &World, entity: Entity, tag_name: &str) -> Option<i32> {
+        world
+            .get::<GameplayTagCountContainer>(entity)
+            .map(|count_container| count_container.get_tag_count(&GameplayTag::new(tag_name)))
+    }
+
+    #[test]
+    fn entity_commands_helpers_apply_with_the_commands() {
+        let (mut world, _) = setup_world();
+        let entity = world.spawn_empty().id();
+        world
+            .commands()
+            .entity(entity)
+            .remove_gameplay_tag(GameplayTag::new("A.B.C"));
+        world.flush();
+        //只移除标签时不会补上标签容器
+        assert_eq!(tag_count(&world, entity, "A.B.C"), None);
+
+        world
+            .commands()
+            .entity(entity)
+            .add_gameplay_tag(GameplayTag::new("A.B.C"))
+            .update_gameplay_tag_count(GameplayTag::new("A.B.C"), 2)
+            .set_gameplay_tag_count(GameplayTag::new("D.C"), 3);
+        assert_eq!(tag_count(&world, entity, "A.B.C"), None);
+        world.flush();
+        assert_eq!(tag_count(&world, entity, "A.B.C"), Some(3));
+        assert_eq!(tag_count(&world, entity, "D"), Some(3));
+        assert_eq!(
+            world.resource::<NewOrRemovedEvents>().0,
+            [
+                ("A.B.C", true),
+                ("A.B", true),
+                ("A", true),
+                ("D.C", true),
+                ("D", true)
+            ]
+            .map(|(tag_name, added)| (tag_name.to_string(), added))
+        );
+    }
+
+    #[test]
+    fn entity_world_mut_helpers_apply_right_away() {
+        let (mut world, entity) = setup_world();
+        world
+            .entity_mut(entity)
+            .remove_gameplay_tag(GameplayTag::new("A.B.C"))
+            .add_gameplay_tag(GameplayTag::new("D.C.B"));
+        assert_eq!(tag_count(&world, entity, "A"), Some(0));
+        assert_eq!(tag_count(&world, entity, "D"), Some(1));
+
+        world
+            .entity_mut(entity)
+            .set_gameplay_tag_count(GameplayTag::new("D.C.B"), 0);
+        assert_eq!(tag_count(&world, entity, "D"), Some(0));
+        assert_eq!(world.resource::<NewOrRemovedEvents>().0.len(), 9);
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::{
+    ecs::{
+        bundle::Bundle,
+        entity::{Entity, EntityHashSet},
+        lifecycle::Remove,
+        observer::On,
+        resource::Resource,
+        system::{Commands, EntityCommands, Query, ResMut},
+        world::{Mut, World},
+    },
+    log::debug,
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_container::{GameplayTagContainer, GameplayTagQuery},
+    gameplay_tag_count_container::{GameplayTagCountContainer, GameplayTagCountNotification},
+};
+
+type EntityCommandsFn = Box<dyn Fn(&mut EntityCommands) + Send + Sync>;
+
+///
+/// A rule that inserts components on an entity while its tags match a query, and cleans them up
+/// once the tags stop matching.
+///
+/// # Examples
+/// ```ignore
+/// let mut rule = GameplayTagComponentRule::for_tag(GameplayTag::new("Status.Frozen"));
+/// rule.insert_bundle(|| Frozen::default())
+///     .on_cleanup(|entity| {
+///         entity.remove::<VelocityOverride>();
+///     });
+/// app.add_gameplay_tag_component_rule(rule);
+/// ```
+///
+pub struct GameplayTagComponentRule {
+    query: GameplayTagQuery,
+    apply_fns: Vec<EntityCommandsFn>,
+    cleanup_fns: Vec<EntityCommandsFn>,
+    active_entities: EntityHashSet,
+    initialized: bool,
+}
+
+impl GameplayTagComponentRule {
+    /// Creates a rule that is active while the entity has `tag` or one of its descendants.
+    pub fn for_tag(tag: GameplayTag) -> Self {
+        let mut tags = GameplayTagContainer::new();
+        tags.gameplay_tags.push(tag);
+        Self::for_query(GameplayTagQuery::make_query_match_any_tags(&tags))
+    }
+
+    /// Creates a rule that is active while the entity's tags match `query`.
+    pub fn for_query(query: GameplayTagQuery) -> Self {
+        GameplayTagComponentRule {
+            query,
+            apply_fns: Vec::new(),
+            cleanup_fns: Vec::new(),
+            active_entities: EntityHashSet::default(),
+            initialized: false,
+        }
+    }
+
+    ///
+    /// Inserts the bundle built by `factory` when the rule becomes active and removes the bundle
+    /// again when it becomes inactive.
+    ///
+    pub fn insert_bundle<B: Bundle>(
+        &mut self,
+        factory: impl Fn() -> B + Send + Sync + 'static,
+    ) -> &mut Self {
+        //命令执行前实体可能已被销毁，使用try_*避免panic
+        self.apply_fns.push(Box::new(move |entity| {
+            entity.try_insert(factory());
+        }));
+        self.cleanup_fns.push(Box::new(|entity| {
+            entity.try_remove::<B>();
+        }));
+        self
+    }
+
+    /// Runs `apply` when the rule becomes active on an entity.
+    pub fn on_apply(
+        &mut self,
+        apply: impl Fn(&mut EntityCommands) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.apply_fns.push(Box::new(apply));
+        self
+    }
+
+    /// Runs `cleanup` when the rule stops being active on an entity.
+    pub fn on_cleanup(
+        &mut self,
+        cleanup: impl Fn(&mut EntityCommands) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.cleanup_fns.push(Box::new(cleanup));
+        self
+    }
+
+    pub fn get_query(&self) -> &GameplayTagQuery {
+        &self.query
+    }
+
+    pub fn is_active(&self, entity: Entity) -> bool {
+        self.active_entities.contains(&entity)
+    }
+
+    fn update_entity(&mut self, entity: Entity, matches: bool, commands: &mut Commands) {
+        if matches {
+            if self.active_entities.insert(entity) {
+                let mut entity_commands = commands.entity(entity);
+                for apply in self.apply_fns.iter() {
+                    apply(&mut entity_commands);
+                }
+            }
+        } else if self.active_entities.remove(&entity) {
+            let mut entity_commands = commands.entity(entity);
+            for cleanup in self.cleanup_fns.iter() {
+                cleanup(&mut entity_commands);
+            }
+        }
+    }
+}
+
+///
+/// Registry of `GameplayTagComponentRule`s, evaluated whenever a tag is newly added to or
+/// completely removed from an entity's `GameplayTagCountContainer`.
+///
+#[derive(Resource, Default)]
+pub struct GameplayTagComponentRules {
+    rules: Vec<GameplayTagComponentRule>,
+}
+
+impl GameplayTagComponentRules {
+    pub fn add_rule(&mut self, rule: GameplayTagComponentRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn evaluate_entity(
+        &mut self,
+        entity: Entity,
+        container: Option<&GameplayTagContainer>,
+        commands: &mut Commands,
+    ) {
+        for rule in self.rules.iter_mut() {
+            let matches = container.is_some_and(|tags| rule.query.matches(tags));
+            rule.update_entity(entity, matches, commands);
+        }
+    }
+}
+
+pub(crate) fn apply_tag_component_rules(
+    trigger: On<GameplayTagCountNotification>,
+    containers: Query<&GameplayTagCountContainer>,
+    mut rules: ResMut<GameplayTagComponentRules>,
+    mut commands: Commands,
+) {
+    let notification = trigger.event();
+    if !notification.has_presence_changes() {
+        return;
+    }
+    let container = containers
+        .get(notification.entity)
+        .ok()
+        .map(|count_container| count_container.get_explicit_tags());
+    rules.evaluate_entity(notification.entity, container, &mut commands);
+}
+
+pub(crate) fn cleanup_tag_component_rules(
+    trigger: On<Remove, GameplayTagCountContainer>,
+    mut rules: ResMut<GameplayTagComponentRules>,
+    mut commands: Commands,
+) {
+    let entity = trigger.event().entity;
+    let mut deactivated = Vec::new();
+    for (index, rule) in rules.rules.iter_mut().enumerate() {
+        if rule.active_entities.remove(&entity) {
+            deactivated.push(index);
+        }
+    }
+    if deactivated.is_empty() {
+        return;
+    }
+
+    //组件移除也可能是实体被销毁，只有实体还存在时才执行清理
+    commands.queue(move |world: &mut World| {
+        if world.get_entity(entity).is_err() {
+            debug!("实体 {} 已销毁，跳过标签组件规则清理", entity);
+            return;
+        }
+        world.resource_scope(|world, rules: Mut<GameplayTagComponentRules>| {
+            let mut commands = world.commands();
+            let mut entity_commands = commands.entity(entity);
+            for index in deactivated {
+                for cleanup in rules.rules[index].cleanup_fns.iter() {
+                    cleanup(&mut entity_commands);
+                }
+            }
+        });
+        world.flush();
+    });
+}
+
+pub(crate) fn initialize_tag_component_rules(
+    containers: Query<(Entity, &GameplayTagCountContainer)>,
+    mut rules: ResMut<GameplayTagComponentRules>,
+    mut commands: Commands,
+) {
+    for rule in rules.rules.iter_mut().filter(|rule| !rule.initialized) {
+        rule.initialized = true;
+        for (entity, count_container) in containers.iter() {
+            let matches = rule.query.matches(count_container.get_explicit_tags());
+            rule.update_entity(entity, matches, &mut commands);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::ecs::{
+    component::Component,
+    entity::Entity,
+    query::With,
+    system::{Query, Res, SystemParam},
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_container::{GameplayTagContainer, GameplayTagQuery},
+    gameplay_tag_count_container::GameplayTagCountContainer,
+    gameplay_tag_query_registry::GameplayTagQueryRegistry,
+    gameplay_tag_requirements::GameplayTagRequirements,
+};
+
+///
+/// Marks the entity whose `GameplayTagContainer` / `GameplayTagCountContainer` holds the global
+/// game tags, such as the current game flow. There should be at most one such entity.
+///
+#[derive(Component, Debug, Default)]
+pub struct GlobalGameplayTags;
+
+type GlobalTagContainers<'w, 's> =
+    Query<'w, 's, &'static GameplayTagContainer, With<GlobalGameplayTags>>;
+type GlobalTagCountContainers<'w, 's> =
+    Query<'w, 's, &'static GameplayTagCountContainer, With<GlobalGameplayTags>>;
+type TagContainers<'w, 's> = Query<'w, 's, &'static GameplayTagContainer>;
+type TagCountContainers<'w, 's> = Query<'w, 's, &'static GameplayTagCountContainer>;
+
+///
+/// The indexes the plugin maintains for `GameplayTagCountContainer`s, used by the run conditions
+/// to avoid scanning every tagged entity.
+///
+#[derive(SystemParam)]
+pub struct GameplayTagConditionIndexes<'w> {
+    query_registry: Option<Res<'w, GameplayTagQueryRegistry>>,
+}
+
+impl GameplayTagConditionIndexes<'_> {
+    ///
+    /// Checks the members of a registered query equal to `query`, if any.
+    ///
+    /// # Returns
+    /// * `None` if no such query is registered yet, otherwise whether it has any member.
+    ///
+    fn any_entity_in_query(&self, query: &GameplayTagQuery) -> Option<bool> {
+        self.query_registry
+            .as_ref()?
+            .get_members_of_query(query)
+            .map(|members| !members.is_empty())
+    }
+}
+
+///
+/// Returns `true` while any entity has `tag`, either explicitly or as the parent of one of its tags.
+///
+/// Every tag container is scanned, so the result always agrees with `entity_has_tag`. Marker
+/// components bound with `GameplayTagsAppExt::add_gameplay_tag_marker` are not used here: they are
+/// inserted and removed through commands and lag behind the containers. For hot paths, filter with
+/// the marker directly, e.g. `|q: Query<(), With<Stunned>>| !q.is_empty()`, if that lag is fine.
+///
+pub fn any_entity_has_tag(
+    tag: GameplayTag,
+) -> impl FnMut(TagContainers, TagCountContainers) -> bool + Clone {
+    move |containers: TagContainers, count_containers: TagCountContainers| {
+        count_containers
+            .iter()
+            .any(|count_container| count_container.has_matching_gameplay_tag(&tag))
+            || containers.iter().any(|container| container.has_tag(&tag))
+    }
+}
+
+/// Returns `true` while `entity` has `tag`, either explicitly or as the parent of one of its tags.
+pub fn entity_has_tag(
+    entity: Entity,
+    tag: GameplayTag,
+) -> impl FnMut(Query<&GameplayTagContainer>, Query<&GameplayTagCountContainer>) -> bool + Clone {
+    move |containers: Query<&GameplayTagContainer>,
+          count_containers: Query<&GameplayTagCountContainer>| {
+        count_containers
+            .get(entity)
+            .is_ok_and(|count_container| count_container.has_matching_gameplay_tag(&tag))
+            || containers
+                .get(entity)
+                .is_ok_and(|container| container.has_tag(&tag))
+    }
+}
+
+///
+/// Returns `true` while any entity's tags match `query`.
+///
+/// If an equal query is registered with `GameplayTagsAppExt::register_gameplay_tag_query`, the
+/// `GameplayTagCountContainer`s are answered by its membership set and only plain
+/// `GameplayTagContainer`s are scanned. Otherwise the query is evaluated against every tagged
+/// entity; for queries that are checked often, register them, or use `any_entity_in_tag_query`.
+///
+pub fn any_entity_matches(
+    query: GameplayTagQuery,
+) -> impl FnMut(GameplayTagConditionIndexes, TagContainers, TagCountContainers) -> bool {
+    move |indexes: GameplayTagConditionIndexes,
+          containers: TagContainers,
+          count_containers: TagCountContainers| {
+        let has_count_container_match = match indexes.any_entity_in_query(&query) {
+            Some(has_member) => has_member,
+            None => count_containers
+                .iter()
+                .any(|count_container| query.matches(count_container.get_explicit_tags())),
+        };
+        has_count_container_match || containers.iter().any(|container| query.matches(container))
+    }
+}
+
+/// Returns `true` while at least one entity is a member of the registered query called `name`.
+pub fn any_entity_in_tag_query(
+    name: impl Into<String>,
+) -> impl FnMut(Res<GameplayTagQueryRegistry>) -> bool + Clone {
+    let name = name.into();
+    move |registry: Res<GameplayTagQueryRegistry>| {
+        registry
+            .get_members_by_name(&name)
+            .is_some_and(|members| !members.is_empty())
+    }
+}
+
+///
+/// Returns `true` while the tags of the `GlobalGameplayTags` entity meet `requirements`.
+/// If there is no such entity, the requirements are checked against an empty container.
+///
+pub fn global_tags_match(
+    requirements: GameplayTagRequirements,
+) -> impl FnMut(GlobalTagContainers, GlobalTagCountContainers) -> bool {
+    move |containers: GlobalTagContainers, count_containers: GlobalTagCountContainers| {
+        if let Some(count_container) = count_containers.iter().next() {
+            requirements.requirements_met(count_container.get_explicit_tags())
+        } else if let Some(container) = containers.iter().next() {
+            requirements.requirements_met(container)
+        } else {
+            requirements.requirements_met(&GameplayTagContainer::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{system::RunSystemOnce, world::World};
+
+    use super::*;
+    use crate::{
+        gameplay_tag_commands::GameplayTagWorldExt, gameplay_tags_manager::GameplayTagsManager,
+    };
+
+    fn update_tag_count(world: &mut World, entity: Entity, tag_name: &str, count_delta: i32) {
+        world
+            .modify_gameplay_tags(entity, |tx| {
+                tx.update_tag_count(&GameplayTag::new(tag_name), count_delta)
+            })
+            .unwrap();
+    }
+
+    fn check(world: &mut World, entity: Entity, tag_name: &str) -> (bool, bool) {
+        let tag = GameplayTag::new(tag_name);
+        let any = world
+            .run_system_once(any_entity_has_tag(tag.clone()))
+            .unwrap();
+        let own = world.run_system_once(entity_has_tag(entity, tag)).unwrap();
+        (any, own)
+    }
+
+    #[test]
+    fn any_entity_has_tag_agrees_with_entity_has_tag() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        assert_eq!(check(&mut world, entity, "A.B"), (false, false));
+
+        update_tag_count(&mut world, entity, "A.B.C", 1);
+        assert_eq!(check(&mut world, entity, "A.B"), (true, true));
+        assert_eq!(check(&mut world, entity, "A.B.C"), (true, true));
+
+        update_tag_count(&mut world, entity, "A.B.C", -1);
+        assert_eq!(check(&mut world, entity, "A.B"), (false, false));
+    }
+}
@@ -1,9 +1,21 @@
 use crate::gameplay_tag::GameplayTag;
 use crate::gameplay_tags_manager::GameplayTagsManager;
+use bevy::ecs::lifecycle::HookContext;
+use bevy::ecs::world::DeferredWorld;
 use bevy::prelude::Component;
-use bevy::prelude::Res;
 
+///
+/// A sorted set of explicit gameplay tags together with their implicit parent tags.
+///
+/// Both tag lists are kept sorted so lookups can use binary search. Prefer `add_tag` /
+/// `remove_tag` and the `get_*` accessors over touching the public fields directly; after
+/// pushing onto `gameplay_tags` by hand, call `normalize` to restore the invariants. When the
+/// container is inserted as a component (for example from a scene), an `on_insert` hook re-sorts
+/// the explicit tags, removes duplicates and rebuilds the parent tags from the
+/// `GameplayTagsManager`.
+///
 #[derive(Component, Debug, Clone)]
+#[component(on_insert = normalize_gameplay_tag_container)]
 pub struct GameplayTagContainer {
     pub gameplay_tags: Vec<GameplayTag>,
     pub parent_tags: Vec<GameplayTag>,
@@ -32,6 +44,38 @@ impl GameplayTagContainer {
         self.parent_tags.clear();
     }
 
+    /// Returns the explicit tags of the container, sorted.
+    pub fn get_gameplay_tags(&self) -> &[GameplayTag] {
+        &self.gameplay_tags
+    }
+
+    /// Returns the implicit parent tags of the explicit tags, sorted.
+    pub fn get_parent_tags(&self) -> &[GameplayTag] {
+        &self.parent_tags
+    }
+
+    /// Returns the number of explicit tags in the container.
+    pub fn num(&self) -> usize {
+        self.gameplay_tags.len()
+    }
+
+    ///
+    /// Restores the container invariants: sorts the explicit tags, removes duplicates and
+    /// invalid tags, and rebuilds the parent tags.
+    ///
+    /// This is run automatically when the container is inserted as a component.
+    ///
+    pub fn normalize(&mut self, tags_manager: &GameplayTagsManager) {
+        self.normalize_explicit_tags();
+        self.fill_parent_tags(tags_manager);
+    }
+
+    fn normalize_explicit_tags(&mut self) {
+        self.gameplay_tags.retain(|tag| tag.is_valid());
+        self.gameplay_tags.sort();
+        self.gameplay_tags.dedup();
+    }
+
     ///
     /// Determines if the current container has a specific gameplay tag.
     /// Includes checking explicit and implicit tags for current container.
@@ -204,19 +248,33 @@ impl GameplayTagContainer {
         }
     }
 
-    pub fn add_tag(&mut self, tag: GameplayTag, tags_manager: &Res<GameplayTagsManager>) {
-        if tag.is_valid() {
-            match self.gameplay_tags.binary_search(&tag) {
-                Ok(_) => {}
-                Err(index) => {
-                    self.gameplay_tags.insert(index, tag.clone());
-                    self.add_parent_tag(tag, tags_manager);
-                }
+    ///
+    /// Adds an explicit tag together with its parent tags. If the tag belongs to an exclusive group
+    /// (see `GameplayTagsManager::set_exclusive_group`), its siblings in that group are removed.
+    ///
+    pub fn add_tag(&mut self, tag: GameplayTag, tags_manager: &GameplayTagsManager) {
+        if !tag.is_valid() {
+            return;
+        }
+        if let Err(index) = self.gameplay_tags.binary_search(&tag) {
+            self.gameplay_tags.insert(index, tag.clone());
+            if !self.remove_exclusive_siblings(&tag, tags_manager) {
+                self.add_parent_tag(tag, tags_manager);
             }
         }
     }
 
-    pub fn add_tag_fast(&mut self, tag: GameplayTag, tags_manager: &Res<GameplayTagsManager>) {
+    ///
+    /// Adds an explicit tag like `add_tag`, but keeps its siblings in exclusive groups. Use this
+    /// for tag sets such as queries and requirements that may list several options of a group.
+    ///
+    pub fn add_tag_non_exclusive(&mut self, tag: GameplayTag, tags_manager: &GameplayTagsManager) {
+        if tag.is_valid() {
+            self.add_tag_fast(tag, tags_manager);
+        }
+    }
+
+    pub fn add_tag_fast(&mut self, tag: GameplayTag, tags_manager: &GameplayTagsManager) {
         match self.gameplay_tags.binary_search(&tag) {
             Ok(_) => {}
             Err(index) => {
@@ -226,7 +284,26 @@ impl GameplayTagContainer {
         }
     }
 
-    pub fn add_parent_tag(&mut self, tag: GameplayTag, tags_manager: &Res<GameplayTagsManager>) {
+    //移除与tag互斥的兄弟标签，有移除时会重建父标签
+    fn remove_exclusive_siblings(
+        &mut self,
+        tag: &GameplayTag,
+        tags_manager: &GameplayTagsManager,
+    ) -> bool {
+        if !tags_manager.has_exclusive_groups() {
+            return false;
+        }
+        let num_tags = self.gameplay_tags.len();
+        self.gameplay_tags
+            .retain(|other| !tags_manager.are_exclusive_siblings(tag, other));
+        if self.gameplay_tags.len() == num_tags {
+            return false;
+        }
+        self.parent_tags = Self::collect_parent_tags(&self.gameplay_tags, tags_manager);
+        true
+    }
+
+    pub fn add_parent_tag(&mut self, tag: GameplayTag, tags_manager: &GameplayTagsManager) {
         let complete_container = tags_manager.get_single_tag_container(&tag);
         if let Some(exist_container) = complete_container {
             for tag in exist_container.parent_tags.iter() {
@@ -270,26 +347,34 @@ impl GameplayTagContainer {
     /// // parent tags from the `gameplay_tags` in a sorted manner.
     /// ```
     ///
-    pub fn fill_parent_tags(&mut self, tags_manager: &Res<GameplayTagsManager>) {
-        self.parent_tags.clear();
-        for tag in self.gameplay_tags.iter() {
+    pub fn fill_parent_tags(&mut self, tags_manager: &GameplayTagsManager) {
+        self.parent_tags = Self::collect_parent_tags(&self.gameplay_tags, tags_manager);
+    }
+
+    fn collect_parent_tags(
+        gameplay_tags: &[GameplayTag],
+        tags_manager: &GameplayTagsManager,
+    ) -> Vec<GameplayTag> {
+        let mut parent_tags: Vec<GameplayTag> = Vec::new();
+        for tag in gameplay_tags.iter() {
             let complete_container = tags_manager.get_single_tag_container(tag);
             if let Some(exist_container) = complete_container {
                 for parent_tag in exist_container.parent_tags.iter() {
-                    match self.parent_tags.binary_search(parent_tag) {
+                    match parent_tags.binary_search(parent_tag) {
                         Ok(_) => {}
-                        Err(index) => self.parent_tags.insert(index, parent_tag.clone()),
+                        Err(index) => parent_tags.insert(index, parent_tag.clone()),
                     }
                 }
             }
         }
+        parent_tags
     }
 
     pub fn remove_tag(
         &mut self,
         tag: &GameplayTag,
         defer_parent_tags: bool,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
     ) -> bool {
         let index = self.find_tag_index(tag);
         match index {
@@ -307,7 +392,7 @@ impl GameplayTagContainer {
     pub fn remove_tags(
         &mut self,
         tags_to_remove: GameplayTagContainer,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
     ) {
         let mut num_changed = 0;
         for tag in tags_to_remove.gameplay_tags.iter() {
@@ -331,7 +416,7 @@ impl GameplayTagContainer {
     /// # Arguments
     /// * `other_a` - A reference to the first `GameplayTagContainer` whose tags are to be appended.
     /// * `other_b` - A reference to the second `GameplayTagContainer` used for matching against `other_a`'s tags.
-    /// * `tags_manager` - A resource reference to the `GameplayTagsManager` used for tag operations.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager` used for tag operations.
     ///
     /// # Details
     /// This method iterates over each tag in `other_a`. For each tag, it checks if there is a match
@@ -350,11 +435,11 @@ impl GameplayTagContainer {
         &mut self,
         other_a: &GameplayTagContainer,
         other_b: &GameplayTagContainer,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
     ) {
         for other_a_tag in other_a.gameplay_tags.iter() {
             if other_a_tag.matches_any(other_b, tags_manager) {
-                self.add_tag(other_a_tag.clone(), tags_manager);
+                self.add_tag_non_exclusive(other_a_tag.clone(), tags_manager);
             }
         }
     }
@@ -365,29 +450,30 @@ impl GameplayTagContainer {
     /// # Arguments
     ///
     /// * `other` - A reference to the `GameplayTagContainer` from which tags will be appended.
-    /// * `tags_manager` - A resource reference to the `GameplayTagsManager` used for managing tag operations.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager` used for managing tag operations.
     ///
     /// This function iterates over each tag in the `other` container and adds it to the current container,
-    /// using the provided `tags_manager` to handle the addition.
+    /// using the provided `tags_manager` to handle the addition. Exclusive groups are not applied, so
+    /// the result is the union of both containers.
     pub fn append_tags(
         &mut self,
         other: &GameplayTagContainer,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
     ) {
         for tag in other.gameplay_tags.iter() {
-            self.add_tag(tag.clone(), tags_manager);
+            self.add_tag_non_exclusive(tag.clone(), tags_manager);
         }
     }
 
     pub fn filter(
         &self,
         other: &GameplayTagContainer,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
     ) -> GameplayTagContainer {
         let mut filtered_tags = GameplayTagContainer::new();
         for tag in self.gameplay_tags.iter() {
             if tag.matches_any(other, tags_manager) {
-                filtered_tags.add_tag(tag.clone(), tags_manager);
+                filtered_tags.add_tag_non_exclusive(tag.clone(), tags_manager);
             }
         }
         filtered_tags
@@ -396,12 +482,12 @@ impl GameplayTagContainer {
     pub fn filter_exact(
         &self,
         other: &GameplayTagContainer,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
     ) -> GameplayTagContainer {
         let mut filtered_tags = GameplayTagContainer::new();
         for tag in self.gameplay_tags.iter() {
             if tag.matches_any_exact(other) {
-                filtered_tags.add_tag(tag.clone(), tags_manager);
+                filtered_tags.add_tag_non_exclusive(tag.clone(), tags_manager);
             }
         }
         filtered_tags
@@ -431,6 +517,31 @@ impl GameplayTagContainer {
     }
 }
 
+//场景、反序列化或直接构造的容器可能未排序或缺少父标签，插入时统一修正
+fn normalize_gameplay_tag_container(mut world: DeferredWorld, context: HookContext) {
+    let Some(mut normalized) = world
+        .get::<GameplayTagContainer>(context.entity)
+        .cloned()
+    else {
+        return;
+    };
+    normalized.normalize_explicit_tags();
+    if let Some(tags_manager) = world.get_resource::<GameplayTagsManager>() {
+        normalized.parent_tags =
+            GameplayTagContainer::collect_parent_tags(&normalized.gameplay_tags, tags_manager);
+    } else {
+        normalized.parent_tags.sort();
+        normalized.parent_tags.dedup();
+    }
+
+    if let Some(mut container) = world.get_mut::<GameplayTagContainer>(context.entity)
+        && (container.gameplay_tags != normalized.gameplay_tags
+            || container.parent_tags != normalized.parent_tags)
+    {
+        *container = normalized;
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum GameplayTagQueryExprType {
     Undefined = 0,
@@ -442,7 +553,7 @@ pub enum GameplayTagQueryExprType {
     NoExprMatch,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct GameplayTagQueryExpression {
     expr_type: GameplayTagQueryExprType,
     expr_set: Vec<GameplayTagQueryExpression>,
@@ -598,7 +709,7 @@ impl GameplayTagQueryExpression {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 #[allow(dead_code)]
 pub struct GameplayTagQuery {
     expr: GameplayTagQueryExpression,
@@ -667,3 +778,42 @@ impl GameplayTagQuery {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::World;
+
+    use super::*;
+
+    fn tags_manager_with_exclusive_group(group: &str) -> GameplayTagsManager {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        let mut tags_manager = world.remove_resource::<GameplayTagsManager>().unwrap();
+        tags_manager.set_exclusive_group(GameplayTag::new(group), true);
+        tags_manager
+    }
+
+    #[test]
+    fn add_tag_replaces_exclusive_siblings() {
+        let tags_manager = tags_manager_with_exclusive_group("A.B");
+        let mut container = GameplayTagContainer::new();
+        container.add_tag(GameplayTag::new("A.B.C"), &tags_manager);
+        container.add_tag(GameplayTag::new("A.B.D"), &tags_manager);
+
+        assert_eq!(container.get_gameplay_tags(), [GameplayTag::new("A.B.D")]);
+        assert!(container.has_tag(&GameplayTag::new("A.B")));
+        assert!(!container.has_tag(&GameplayTag::new("A.B.C")));
+    }
+
+    #[test]
+    fn add_tag_non_exclusive_keeps_exclusive_siblings() {
+        let tags_manager = tags_manager_with_exclusive_group("A.B");
+        let mut container = GameplayTagContainer::new();
+        container.add_tag_non_exclusive(GameplayTag::new("A.B.C"), &tags_manager);
+        container.add_tag_non_exclusive(GameplayTag::new("A.B.D"), &tags_manager);
+
+        assert_eq!(container.num(), 2);
+        assert!(container.has_tag_exact(&GameplayTag::new("A.B.C")));
+        assert!(container.has_tag_exact(&GameplayTag::new("A.B.D")));
+    }
+}
@@ -0,0 +1,559 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::{
+        change_detection::DetectChangesMut,
+        entity::{Entities, Entity, EntityHashMap, EntityHashSet},
+        event::EntityEvent,
+        lifecycle::Remove,
+        observer::On,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+        world::CommandQueue,
+    },
+    log::{debug, warn},
+    platform::collections::HashSet,
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_container::GameplayTagQuery,
+    gameplay_tag_count_container::{
+        GameplayTagCountContainer, GameplayTagCountNotification, GameplayTagCountUpdateResult,
+    },
+    gameplay_tag_timed_grants::TimedGameplayTags,
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+/// A tag operation applied when a `GameplayTagConversionRule` fires.
+#[derive(Debug, Clone)]
+pub enum GameplayTagConversionOp {
+    AddTag {
+        tag: GameplayTag,
+        count: i32,
+    },
+    RemoveTag {
+        tag: GameplayTag,
+        count: i32,
+    },
+    SetTagCount {
+        tag: GameplayTag,
+        count: i32,
+    },
+    /// Removes all stacks of every explicit tag matching `tag`, i.e. `tag` and its descendants.
+    RemoveMatchingTags {
+        tag: GameplayTag,
+    },
+    /// Grants `count` stacks of `tag` through `TimedGameplayTags` for `duration`.
+    AddTimedTag {
+        tag: GameplayTag,
+        count: i32,
+        duration: Duration,
+    },
+}
+
+///
+/// A rule that converts tags once their counts reach thresholds.
+///
+/// A rule fires when every count requirement is met and the entity's tags match the optional query.
+/// All of its operations are then applied in order, before any other rule is evaluated.
+///
+/// # Examples
+/// ```ignore
+/// let mut rule = GameplayTagConversionRule::new("Freeze");
+/// rule.require_tag_count(GameplayTag::new("Element.Wet"), 3)
+///     .require_tag_count(GameplayTag::new("Element.Cold"), 1)
+///     .remove_tag(GameplayTag::new("Element.Wet"), 3)
+///     .remove_matching_tags(GameplayTag::new("Element.Cold"))
+///     .add_timed_tag(GameplayTag::new("Status.Frozen"), 1, Duration::from_secs(2));
+/// app.add_gameplay_tag_conversion_rule(rule);
+/// ```
+///
+#[derive(Debug)]
+pub struct GameplayTagConversionRule {
+    name: String,
+    required_counts: Vec<(GameplayTag, i32)>,
+    query: GameplayTagQuery,
+    ops: Vec<GameplayTagConversionOp>,
+}
+
+impl GameplayTagConversionRule {
+    pub fn new(name: impl Into<String>) -> Self {
+        GameplayTagConversionRule {
+            name: name.into(),
+            required_counts: Vec::new(),
+            query: GameplayTagQuery::new(),
+            ops: Vec::new(),
+        }
+    }
+
+    ///
+    /// Requires the count of `tag` to be at least `min_count`. Counts include descendants, so
+    /// requiring `Element.Cold` is met by any `Element.Cold.*` tag.
+    ///
+    pub fn require_tag_count(&mut self, tag: GameplayTag, min_count: i32) -> &mut Self {
+        self.required_counts.push((tag, min_count));
+        self
+    }
+
+    /// Requires the entity's tags to match `query`.
+    pub fn require_query(&mut self, query: GameplayTagQuery) -> &mut Self {
+        self.query = query;
+        self
+    }
+
+    pub fn add_tag(&mut self, tag: GameplayTag, count: i32) -> &mut Self {
+        self.ops
+            .push(GameplayTagConversionOp::AddTag { tag, count });
+        self
+    }
+
+    pub fn remove_tag(&mut self, tag: GameplayTag, count: i32) -> &mut Self {
+        self.ops
+            .push(GameplayTagConversionOp::RemoveTag { tag, count });
+        self
+    }
+
+    pub fn set_tag_count(&mut self, tag: GameplayTag, count: i32) -> &mut Self {
+        self.ops
+            .push(GameplayTagConversionOp::SetTagCount { tag, count });
+        self
+    }
+
+    pub fn remove_matching_tags(&mut self, tag: GameplayTag) -> &mut Self {
+        self.ops
+            .push(GameplayTagConversionOp::RemoveMatchingTags { tag });
+        self
+    }
+
+    pub fn add_timed_tag(&mut self, tag: GameplayTag, count: i32, duration: Duration) -> &mut Self {
+        self.ops.push(GameplayTagConversionOp::AddTimedTag {
+            tag,
+            count,
+            duration,
+        });
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_ops(&self) -> &[GameplayTagConversionOp] {
+        &self.ops
+    }
+
+    /// Returns `true` if the rule's requirements are met by `count_container`.
+    pub fn is_met(&self, count_container: &GameplayTagCountContainer) -> bool {
+        !self.ops.is_empty()
+            && self
+                .required_counts
+                .iter()
+                .all(|(tag, min_count)| count_container.get_visible_tag_count(tag) >= *min_count)
+            && self.query.matches(count_container.get_explicit_tags())
+    }
+
+    ///
+    /// Applies the operations in order.
+    ///
+    /// # Returns
+    /// * `false` as soon as an addition is blocked or rejected, in which case the remaining
+    ///   operations are skipped and the caller has to roll back the ones already applied.
+    ///
+    fn apply(
+        &self,
+        count_container: &mut GameplayTagCountContainer,
+        timed_tags: &mut Option<TimedGameplayTags>,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) -> bool {
+        for op in self.ops.iter() {
+            let applied = match op {
+                GameplayTagConversionOp::AddTag { tag, count } => !is_addition_refused(
+                    count_container.update_tag_count(tag, *count, tags_manager, commands, entity),
+                ),
+                GameplayTagConversionOp::RemoveTag { tag, count } => {
+                    count_container.update_tag_count(tag, -count, tags_manager, commands, entity);
+                    true
+                }
+                GameplayTagConversionOp::SetTagCount { tag, count } => !is_addition_refused(
+                    count_container.set_tag_count(tag, *count, tags_manager, commands, entity),
+                ),
+                GameplayTagConversionOp::RemoveMatchingTags { tag } => {
+                    let matching_tags: Vec<GameplayTag> = count_container
+                        .get_explicit_tag_counts()
+                        .map(|(explicit_tag, _)| explicit_tag)
+                        .filter(|explicit_tag| explicit_tag.matches_tag(tag, tags_manager))
+                        .cloned()
+                        .collect();
+                    for matching_tag in matching_tags {
+                        count_container.set_tag_count(
+                            &matching_tag,
+                            0,
+                            tags_manager,
+                            commands,
+                            entity,
+                        );
+                    }
+                    true
+                }
+                GameplayTagConversionOp::AddTimedTag {
+                    tag,
+                    count,
+                    duration,
+                } => timed_tags
+                    .get_or_insert_default()
+                    .add_timed_tag(
+                        tag,
+                        *count,
+                        *duration,
+                        count_container,
+                        tags_manager,
+                        commands,
+                        entity,
+                    )
+                    .is_some(),
+            };
+            if !applied {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn is_addition_refused(result: GameplayTagCountUpdateResult) -> bool {
+    matches!(
+        result,
+        GameplayTagCountUpdateResult::Blocked | GameplayTagCountUpdateResult::Rejected
+    )
+}
+
+///
+/// Registry of `GameplayTagConversionRule`s, evaluated after an entity's
+/// `GameplayTagCountContainer` counts changed.
+///
+/// A rule fires once when its requirements become met, and only fires again after they stopped
+/// being met in between, so a rule whose requirements stay met does not fire on every update.
+/// Rules are checked in the order they were added, and the first one that newly became met fires.
+/// Evaluation then starts over, until no rule newly became met or `max_conversions_per_update`
+/// rules have fired for the entity, which guards against rules converting tags back and forth
+/// forever.
+///
+/// A rule fires atomically: if one of its additions is blocked or rejected by a stacking policy,
+/// every operation it already applied is rolled back without triggering any events, including the
+/// `GameplayTagBlocked` or `GameplayTagStackLimitReached` event of the refused addition, and the
+/// rule is tried again on the next evaluation of the entity.
+///
+#[derive(Resource, Debug)]
+pub struct GameplayTagConversionRules {
+    rules: Vec<GameplayTagConversionRule>,
+    pub max_conversions_per_update: usize,
+    dirty_entities: EntityHashSet,
+    //每个实体上一次评估后满足条件的规则，只有从不满足变为满足时才会触发
+    met_rules: EntityHashMap<HashSet<usize>>,
+}
+
+impl Default for GameplayTagConversionRules {
+    fn default() -> Self {
+        GameplayTagConversionRules {
+            rules: Vec::new(),
+            max_conversions_per_update: 16,
+            dirty_entities: EntityHashSet::default(),
+            met_rules: EntityHashMap::default(),
+        }
+    }
+}
+
+impl GameplayTagConversionRules {
+    pub fn add_rule(&mut self, rule: GameplayTagConversionRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn get_rules(&self) -> &[GameplayTagConversionRule] {
+        &self.rules
+    }
+
+    /// Queues `entity` for evaluation on the next update, e.g. after its rules' inputs changed.
+    pub fn mark_dirty(&mut self, entity: Entity) {
+        self.dirty_entities.insert(entity);
+    }
+
+    ///
+    /// Fires the rules that newly became met on `count_container`, until none is left. The
+    /// commands of a rule, including its events, are only added to `commands` if it fired.
+    ///
+    /// # Returns
+    /// * The names of the rules that fired, in order.
+    ///
+    pub fn evaluate(
+        &mut self,
+        count_container: &mut GameplayTagCountContainer,
+        timed_tags: &mut Option<TimedGameplayTags>,
+        tags_manager: &GameplayTagsManager,
+        entities: &Entities,
+        commands: &mut Commands,
+        entity: Entity,
+    ) -> Vec<String> {
+        let mut already_met = self.met_rules.remove(&entity).unwrap_or_default();
+        let mut failed_rules = HashSet::new();
+        let mut fired_rules = Vec::new();
+        loop {
+            //不再满足的规则重新变为可触发
+            already_met.retain(|index| self.rules[*index].is_met(count_container));
+            let Some(index) = (0..self.rules.len()).find(|index| {
+                !already_met.contains(index)
+                    && !failed_rules.contains(index)
+                    && self.rules[*index].is_met(count_container)
+            }) else {
+                break;
+            };
+            if fired_rules.len() >= self.max_conversions_per_update {
+                warn!(
+                    "实体 {} 的标签转换规则在一次更新中触发超过 {} 次，可能存在循环：{:?}",
+                    entity, self.max_conversions_per_update, fired_rules
+                );
+                break;
+            }
+
+            let rule = &self.rules[index];
+            //先保存快照，任何一步失败都整体回滚；每次尝试使用单独的命令队列，成功后才追加
+            let count_container_snapshot = count_container.clone();
+            let timed_tags_snapshot = timed_tags.clone();
+            let mut attempt_queue = CommandQueue::default();
+            let mut attempt_commands = Commands::new_from_entities(&mut attempt_queue, entities);
+            count_container.begin_batch();
+            if !rule.apply(
+                count_container,
+                timed_tags,
+                tags_manager,
+                &mut attempt_commands,
+                entity,
+            ) {
+                debug!(
+                    "实体 {} 的标签转换规则 {} 执行失败，已回滚",
+                    entity, rule.name
+                );
+                //快照中没有这次尝试记录的事件，回滚后它们不会再被触发
+                *count_container = count_container_snapshot;
+                *timed_tags = timed_tags_snapshot;
+                failed_rules.insert(index);
+                continue;
+            }
+            count_container.commit(tags_manager, &mut attempt_commands, entity);
+            commands.append(&mut attempt_queue);
+            debug!("实体 {} 触发标签转换规则：{}", entity, rule.name);
+            commands.trigger(GameplayTagConversionApplied {
+                entity,
+                rule_name: rule.name.clone(),
+            });
+            fired_rules.push(rule.name.clone());
+            already_met.insert(index);
+        }
+
+        //失败的规则不记录，下次评估时重试
+        let met_rules: HashSet<usize> = (0..self.rules.len())
+            .filter(|index| {
+                !failed_rules.contains(index) && self.rules[*index].is_met(count_container)
+            })
+            .collect();
+        if !met_rules.is_empty() {
+            self.met_rules.insert(entity, met_rules);
+        }
+        fired_rules
+    }
+}
+
+/// Triggered on an entity after a `GameplayTagConversionRule` fired on it.
+#[derive(EntityEvent, Debug)]
+pub struct GameplayTagConversionApplied {
+    pub entity: Entity,
+    pub rule_name: String,
+}
+
+pub(crate) fn mark_gameplay_tag_conversion_dirty(
+    trigger: On<GameplayTagCountNotification>,
+    mut rules: ResMut<GameplayTagConversionRules>,
+) {
+    //规则按可见计数判断，而抑制引起的可见性变化只有NewOrRemoved，没有AnyCountChanged，
+    //所以不区分事件类型，任何通知都标记实体
+    if !rules.rules.is_empty() {
+        rules.dirty_entities.insert(trigger.event().entity);
+    }
+}
+
+pub(crate) fn forget_gameplay_tag_conversion_state(
+    trigger: On<Remove, GameplayTagCountContainer>,
+    mut rules: ResMut<GameplayTagConversionRules>,
+) {
+    let entity = trigger.event().entity;
+    rules.dirty_entities.remove(&entity);
+    rules.met_rules.remove(&entity);
+}
+
+pub(crate) fn apply_gameplay_tag_conversions(
+    mut query: Query<(
+        &mut GameplayTagCountContainer,
+        Option<&mut TimedGameplayTags>,
+    )>,
+    mut rules: ResMut<GameplayTagConversionRules>,
+    tags_manager: Res<GameplayTagsManager>,
+    entities: &Entities,
+    mut commands: Commands,
+) {
+    if rules.dirty_entities.is_empty() {
+        return;
+    }
+    let mut dirty_entities: Vec<Entity> = std::mem::take(&mut rules.dirty_entities)
+        .into_iter()
+        .collect();
+    //按实体排序，保证规则的执行顺序稳定
+    dirty_entities.sort();
+    for entity in dirty_entities {
+        let Ok((mut count_container, timed_tags)) = query.get_mut(entity) else {
+            continue;
+        };
+        match timed_tags {
+            Some(mut timed_tags) => {
+                //只有规则真正触发时才标记计时组件变化
+                let mut entity_timed_tags =
+                    Some(std::mem::take(timed_tags.bypass_change_detection()));
+                let fired_rules = rules.evaluate(
+                    &mut count_container,
+                    &mut entity_timed_tags,
+                    &tags_manager,
+                    entities,
+                    &mut commands,
+                    entity,
+                );
+                *timed_tags.bypass_change_detection() = entity_timed_tags.unwrap_or_default();
+                if !fired_rules.is_empty() {
+                    timed_tags.set_changed();
+                }
+            }
+            None => {
+                let mut entity_timed_tags = None;
+                rules.evaluate(
+                    &mut count_container,
+                    &mut entity_timed_tags,
+                    &tags_manager,
+                    entities,
+                    &mut commands,
+                    entity,
+                );
+                if let Some(entity_timed_tags) = entity_timed_tags {
+                    commands.entity(entity).try_insert(entity_timed_tags);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{
+        observer::On,
+        resource::Resource,
+        world::{DeferredWorld, World},
+    };
+
+    use super::*;
+    use crate::{
+        gameplay_tag_blocking::GameplayTagBlocked, gameplay_tag_commands::GameplayTagWorldExt,
+        gameplay_tag_count_container::OnGameplayEffectTagCountChanged,
+    };
+
+    #[derive(Resource, Default)]
+    struct TriggeredEvents {
+        blocked: usize,
+        tag_changes: usize,
+    }
+
+    fn setup_world(owned_tags: &[&str]) -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<TriggeredEvents>();
+        world.add_observer(|_: On<GameplayTagBlocked>, mut world: DeferredWorld| {
+            world.resource_mut::<TriggeredEvents>().blocked += 1;
+        });
+        world.add_observer(
+            |_: On<OnGameplayEffectTagCountChanged>, mut world: DeferredWorld| {
+                world.resource_mut::<TriggeredEvents>().tag_changes += 1;
+            },
+        );
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        world.modify_gameplay_tags(entity, |tx| {
+            for tag_name in owned_tags {
+                tx.add_tag(&GameplayTag::new(tag_name), 1);
+            }
+        });
+        *world.resource_mut::<TriggeredEvents>() = TriggeredEvents::default();
+        (world, entity)
+    }
+
+    fn evaluate(world: &mut World, entity: Entity, rules: &mut GameplayTagConversionRules) {
+        let mut count_container = world
+            .get::<GameplayTagCountContainer>(entity)
+            .unwrap()
+            .clone();
+        let mut queue = CommandQueue::default();
+        {
+            let tags_manager = world.resource::<GameplayTagsManager>();
+            let mut commands = Commands::new(&mut queue, world);
+            rules.evaluate(
+                &mut count_container,
+                &mut None,
+                tags_manager,
+                world.entities(),
+                &mut commands,
+                entity,
+            );
+        }
+        world.entity_mut(entity).insert(count_container);
+        queue.apply(world);
+    }
+
+    fn convert_rules() -> GameplayTagConversionRules {
+        let mut rule = GameplayTagConversionRule::new("Convert");
+        rule.require_tag_count(GameplayTag::new("A.B.C"), 1)
+            .remove_tag(GameplayTag::new("A.B.C"), 1)
+            .add_tag(GameplayTag::new("Buff.Strength"), 1);
+        let mut rules = GameplayTagConversionRules::default();
+        rules.add_rule(rule);
+        rules
+    }
+
+    #[test]
+    fn rolled_back_rule_triggers_no_events() {
+        let (mut world, entity) = setup_world(&["A.B.C", "D.C"]);
+        world
+            .resource_mut::<GameplayTagsManager>()
+            .add_tag_block(GameplayTag::new("D"), GameplayTag::new("Buff.Strength"));
+        evaluate(&mut world, entity, &mut convert_rules());
+
+        let triggered_events = world.resource::<TriggeredEvents>();
+        assert_eq!(triggered_events.blocked, 0);
+        assert_eq!(triggered_events.tag_changes, 0);
+        let count_container = world.get::<GameplayTagCountContainer>(entity).unwrap();
+        assert_eq!(count_container.get_tag_count(&GameplayTag::new("A.B.C")), 1);
+        assert_eq!(
+            count_container.get_tag_count(&GameplayTag::new("Buff.Strength")),
+            0
+        );
+    }
+
+    #[test]
+    fn fired_rule_triggers_its_events() {
+        let (mut world, entity) = setup_world(&["A.B.C"]);
+        evaluate(&mut world, entity, &mut convert_rules());
+
+        assert!(world.resource::<TriggeredEvents>().tag_changes > 0);
+        let count_container = world.get::<GameplayTagCountContainer>(entity).unwrap();
+        assert_eq!(count_container.get_tag_count(&GameplayTag::new("A.B.C")), 0);
+        assert_eq!(
+            count_container.get_tag_count(&GameplayTag::new("Buff.Strength")),
+            1
+        );
+    }
+}
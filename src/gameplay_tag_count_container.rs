@@ -1,28 +1,66 @@
+use std::time::Duration;
+
 use bevy::{
     ecs::{
+        change_detection::DetectChangesMut,
         component::Component,
         entity::Entity,
         event::EntityEvent,
+        message::{Message, MessageWriter},
         observer::{ObservedBy, Observer},
-        system::{Commands, Res},
+        system::{Commands, Query},
         world::World,
     },
     log::warn,
-    platform::collections::HashMap,
+    platform::collections::{HashMap, HashSet, hash_map::Entry},
 };
 
 use crate::{
-    gameplay_tag::GameplayTag, gameplay_tag_container::GameplayTagContainer,
+    gameplay_tag::GameplayTag,
+    gameplay_tag_blocking::GameplayTagBlocked,
+    gameplay_tag_container::GameplayTagContainer,
+    gameplay_tag_sources::register_gameplay_tag_grant,
+    gameplay_tag_stacking::{GameplayTagStackLimitReached, GameplayTagStackOverflowPolicy},
     gameplay_tags_manager::GameplayTagsManager,
 };
 
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct GameplayTagCountContainer {
     //所有标签的计数，包括父标签，比如添加A.B,这里就不仅A.B计数+1，父标签A也会+1
     gameplay_tag_count_map: HashMap<GameplayTag, i32>,
     //显示标签计数，只添加标签本身计数，不包括父标签。比如添加A.B,这里就只有A.B计数+1
     explicit_tag_count_map: HashMap<GameplayTag, i32>,
+    //未被抑制的显式标签及其父标签，用于匹配和查询
     explicit_tags: GameplayTagContainer,
+    //当前被抑制的显式标签，计数保留但不参与匹配
+    inhibited_tags: HashSet<GameplayTag>,
+    //被抑制的显式标签贡献的计数（包括父标签），可见计数 = 总计数 - 抑制计数
+    inhibited_tag_count_map: HashMap<GameplayTag, i32>,
+    //配置了层数衰减的标签，距离上次衰减（或上次叠加）经过的时间
+    stack_decay_timers: HashMap<GameplayTag, Duration>,
+    //来源实体授予的显式标签计数，来源实体销毁时据此移除它的贡献
+    source_tag_counts: HashMap<Entity, HashMap<GameplayTag, i32>>,
+    //批量模式下不逐个触发事件，而是记录每个标签第一次变化前的计数，每帧汇总发送
+    batch_change_events: bool,
+    batched_changes: HashMap<GameplayTag, PendingTagChange>,
+    //begin_batch的嵌套层数，大于0时处于事务中
+    transaction_depth: u32,
+    //事务中每个标签第一次变化前的计数，commit时据此得到净变化
+    transaction_changes: HashMap<GameplayTag, PendingTagChange>,
+    //事务中被屏蔽或达到叠加上限的事件，commit时和净变化一起触发
+    transaction_refusals: Vec<GameplayTagRefusal>,
+    //一次更新或一次提交中的所有变化，结束时合并为一个内部通知
+    pending_notification: Vec<OnGameplayEffectTagCountChanged>,
+    //为true时只为显式变化的标签触发事件，不为其隐式父标签触发
+    suppress_implicit_parent_events: bool,
+    //按来源更新期间记录当前来源实体，用于填充事件的source
+    current_source: Option<Entity>,
+}
+
+impl Default for GameplayTagCountContainer {
+    fn default() -> Self {
+        GameplayTagCountContainer::new()
+    }
 }
 
 impl GameplayTagCountContainer {
@@ -31,6 +69,18 @@ impl GameplayTagCountContainer {
             gameplay_tag_count_map: HashMap::new(),
             explicit_tag_count_map: HashMap::new(),
             explicit_tags: GameplayTagContainer::new(),
+            inhibited_tags: HashSet::new(),
+            inhibited_tag_count_map: HashMap::new(),
+            stack_decay_timers: HashMap::new(),
+            source_tag_counts: HashMap::new(),
+            batch_change_events: false,
+            batched_changes: HashMap::new(),
+            transaction_depth: 0,
+            transaction_changes: HashMap::new(),
+            transaction_refusals: Vec::new(),
+            pending_notification: Vec::new(),
+            suppress_implicit_parent_events: false,
+            current_source: None,
         }
     }
 
@@ -57,12 +107,11 @@ impl GameplayTagCountContainer {
     ///
     /// This function looks up the given `tag_to_check` in the internal `gameplay_tag_count_map` of the object. It returns `true`
     /// only if the tag exists in the map and its associated count is more than zero, indicating that the tag is indeed
-    /// active or present on the object.
+    /// active or present on the object. Counts held only by inhibited tags are not taken into account.
     ///
     #[inline]
     pub fn has_matching_gameplay_tag(&self, tag_to_check: &GameplayTag) -> bool {
-        let count = self.gameplay_tag_count_map.get(tag_to_check).copied();
-        count.is_some() && count.unwrap() > 0
+        self.get_visible_tag_count(tag_to_check) > 0
     }
 
     ///
@@ -85,8 +134,7 @@ impl GameplayTagCountContainer {
         }
 
         for tag in tag_container.gameplay_tags.iter() {
-            let count = self.gameplay_tag_count_map.get(tag).copied();
-            if count.is_none() || count.unwrap() == 0 {
+            if self.get_visible_tag_count(tag) <= 0 {
                 return false;
             }
         }
@@ -114,8 +162,7 @@ impl GameplayTagCountContainer {
         }
 
         for tag in tag_container.gameplay_tags.iter() {
-            let count = self.gameplay_tag_count_map.get(tag).copied();
-            if count.is_some() && count.unwrap() > 0 {
+            if self.get_visible_tag_count(tag) > 0 {
                 return true;
             }
         }
@@ -133,7 +180,7 @@ impl GameplayTagCountContainer {
     /// # Arguments
     /// * `container` - A reference to the `GameplayTagContainer` whose tags' counts are to be updated.
     /// * `count_delta` - The amount by which to update the count of each tag in the container. Can be positive or negative.
-    /// * `tags_manager` - A resource containing the `GameplayTagsManager` used to manage gameplay tags, including their parent-child relationships.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager` used to manage gameplay tags, including their parent-child relationships.
     /// * `commands` - A mutable reference to the `Commands` struct used to queue commands for later execution within the Bevy ECS (Entity-Component-System) framework.
     /// * `entity` - The `Entity` associated with the tag updates. Used to identify the entity for which the tag map is being updated.
     ///
@@ -147,20 +194,22 @@ impl GameplayTagCountContainer {
         &mut self,
         container: &GameplayTagContainer,
         count_delta: i32,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
         commands: &mut Commands,
         entity: Entity,
     ) {
         if count_delta != 0 {
             let mut updated_any = false;
             for tag in container.gameplay_tags.iter() {
-                updated_any |= self.update_tag_map_deferred_parent_removal_internal(
-                    tag,
-                    count_delta,
-                    tags_manager,
-                    commands,
-                    entity,
-                )
+                updated_any |= self
+                    .update_tag_map_deferred_parent_removal_internal(
+                        tag,
+                        count_delta,
+                        tags_manager,
+                        commands,
+                        entity,
+                    )
+                    .is_changed()
             }
             //因为如果是减少，则有可能某个标签为0被删除，而上面update的里面的remove_tag默认使用的是延迟重建父级（defer_parent_tags_on_remove），所以这里要更新父级。
             //如果是增加，上面update的里面的add_tag会自动添加父级，所以不用管
@@ -176,30 +225,34 @@ impl GameplayTagCountContainer {
     /// # Arguments
     ///
     /// * `tag` - A reference to the `GameplayTag` to be updated.
-    /// * `count_delta` - The change in count to apply to the tag. If this is 0, the function returns `false` without making any changes.
-    /// * `tags_manager` - A resource reference to the `GameplayTagsManager` that manages all gameplay tags.
+    /// * `count_delta` - The change in count to apply to the tag. If this is 0, the function returns `Unchanged` without making any changes.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager` that manages all gameplay tags.
     /// * `commands` - A mutable reference to `Commands` used to queue commands for the Bevy ECS (Entity Component System).
     /// * `entity` - The `Entity` for which the tag count is being updated.
     ///
     /// # Returns
     ///
-    /// * `true` if the tag count was successfully updated and the operation resulted in a non-zero delta.
-    /// * `false` if `count_delta` is 0, indicating no update was necessary or performed.
+    /// * A `GameplayTagCountUpdateResult` describing how much of `count_delta` was applied.
+    ///   Additions limited by the tag's `GameplayTagStackingPolicy` return `Saturated` or `Rejected`
+    ///   and also trigger `GameplayTagStackLimitReached`.
+    ///
+    /// Adding a tag that belongs to an exclusive group removes all stacks of its siblings in that
+    /// group afterwards, with the usual removal events.
     ///
-    /// This method checks if the `count_delta` is not zero before attempting to update the tag count. If an update is needed, it calls `update_tag_map_internal` to perform the actual update. Otherwise, it returns `false` immediately.
+    /// This method checks if the `count_delta` is not zero before attempting to update the tag count. If an update is needed, it calls `update_tag_map_internal` to perform the actual update. Otherwise, it returns `Unchanged` immediately.
     #[inline]
     pub fn update_tag_count(
         &mut self,
         tag: &GameplayTag,
         count_delta: i32,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
         commands: &mut Commands,
         entity: Entity,
-    ) -> bool {
+    ) -> GameplayTagCountUpdateResult {
         if count_delta != 0 {
             self.update_tag_map_internal(tag, count_delta, tags_manager, commands, entity)
         } else {
-            false
+            GameplayTagCountUpdateResult::Unchanged
         }
     }
 
@@ -210,28 +263,28 @@ impl GameplayTagCountContainer {
     ///
     /// * `tag` - A reference to the `GameplayTag` whose count is to be updated.
     /// * `count_delta` - The change in count for the specified tag. Positive values increase the count, while negative values decrease it.
-    /// * `tags_manager` - A resource reference to the `GameplayTagsManager` that manages all gameplay tags and their relationships.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager` that manages all gameplay tags and their relationships.
     /// * `commands` - A mutable reference to `Commands` used to queue commands for entity modification.
     /// * `entity` - The `Entity` to which the tag count update applies.
     ///
     /// # Returns
     ///
-    /// * `bool` - Returns `true` if the tag's count was successfully updated and any required parent tag removals were deferred. Returns `false` if no update was made (e.g., when `count_delta` is 0).
+    /// * `GameplayTagCountUpdateResult` - How much of `count_delta` was applied. Returns `Unchanged` if no update was made (e.g., when `count_delta` is 0).
     ///
     /// # Notes
     ///
     /// - This function does not immediately remove tags from the entity. Instead, it defers the removal of parent tags based on the new count, allowing for more complex tag management scenarios.
-    /// - If `count_delta` is 0, the function returns `false` without making any changes, as there is no need to update the tag count or defer any operations.
+    /// - If `count_delta` is 0, the function returns `Unchanged` without making any changes, as there is no need to update the tag count or defer any operations.
     ///
     #[inline]
     pub fn update_tag_count_deferred_parent_removal(
         &mut self,
         tag: &GameplayTag,
         count_delta: i32,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
         commands: &mut Commands,
         entity: Entity,
-    ) -> bool {
+    ) -> GameplayTagCountUpdateResult {
         if count_delta != 0 {
             self.update_tag_map_deferred_parent_removal_internal(
                 tag,
@@ -241,7 +294,7 @@ impl GameplayTagCountContainer {
                 entity,
             )
         } else {
-            false
+            GameplayTagCountUpdateResult::Unchanged
         }
     }
 
@@ -252,25 +305,25 @@ impl GameplayTagCountContainer {
     ///
     /// * `tag` - A reference to the `GameplayTag` whose count is to be set.
     /// * `new_count` - The new count for the specified tag.
-    /// * `tags_manager` - A resource reference to the `GameplayTagsManager` used for managing tags.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager` used for managing tags.
     /// * `commands` - A mutable reference to `Commands` for executing commands on the world.
     /// * `entity` - The `Entity` for which the tag count is being set.
     ///
     /// # Returns
     ///
-    /// * `bool` - Returns `true` if the tag count was updated (i.e., there was a change in the count), otherwise `false`.
+    /// * `GameplayTagCountUpdateResult` - How the count changed. A `new_count` above the tag's stacking limit is handled like any other addition.
     ///
-    /// This function checks if the current count of the given tag differs from the new count. If it does, it updates the internal tag map and performs any necessary operations through the provided `Commands`. If the new count is the same as the existing one, no action is taken, and `false` is returned.
+    /// This function checks if the current count of the given tag differs from the new count. If it does, it updates the internal tag map and performs any necessary operations through the provided `Commands`. If the new count is the same as the existing one, no action is taken, and `Unchanged` is returned.
     ///
     #[inline]
     pub fn set_tag_count(
         &mut self,
         tag: &GameplayTag,
         new_count: i32,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
         commands: &mut Commands,
         entity: Entity,
-    ) -> bool {
+    ) -> GameplayTagCountUpdateResult {
         let mut existing_count = 0;
         if let Some(count) = self.explicit_tag_count_map.get(tag) {
             existing_count = *count;
@@ -280,7 +333,144 @@ impl GameplayTagCountContainer {
         if count_delta != 0 {
             self.update_tag_map_internal(tag, count_delta, tags_manager, commands, entity)
         } else {
-            false
+            GameplayTagCountUpdateResult::Unchanged
+        }
+    }
+
+    ///
+    /// Updates the count of `tag` like `update_tag_count`, attributing the change to `source`.
+    ///
+    /// The first grant from `source` marks it with a `GameplayTagGrantor` component. Once that
+    /// component is removed, or `source` is despawned, everything `source` still contributes is
+    /// removed again. A negative `count_delta` only removes stacks that `source` granted itself.
+    ///
+    /// Stacks removed without a source, e.g. by `update_tag_count` or stack decay, are taken from
+    /// the unattributed count first and then from the sources in `Entity` order, so a source never
+    /// holds more stacks than are actually present.
+    ///
+    /// # Returns
+    /// * A `GameplayTagCountUpdateResult` describing how much of `count_delta` was applied.
+    ///
+    pub fn update_tag_count_from_source(
+        &mut self,
+        tag: &GameplayTag,
+        count_delta: i32,
+        source: Entity,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) -> GameplayTagCountUpdateResult {
+        let source_count = self.get_source_tag_count(source, tag);
+        let count_delta = count_delta.max(-source_count);
+        if count_delta == 0 {
+            return GameplayTagCountUpdateResult::Unchanged;
+        }
+
+        //先扣除来源自己的计数，避免移除时被当作其他来源或匿名的层数
+        if count_delta < 0 {
+            self.add_source_tag_count(source, tag, count_delta);
+        }
+        self.current_source = Some(source);
+        let result = self.update_tag_map_internal(tag, count_delta, tags_manager, commands, entity);
+        self.current_source = None;
+        let applied_delta = result.get_applied_delta();
+        if applied_delta > 0 {
+            let is_new_source = !self.source_tag_counts.contains_key(&source);
+            self.add_source_tag_count(source, tag, applied_delta);
+            if is_new_source {
+                register_gameplay_tag_grant(commands, source, entity);
+            }
+        }
+        result
+    }
+
+    ///
+    /// Removes every stack `source` granted through `update_tag_count_from_source`.
+    ///
+    /// # Returns
+    /// * `true` if any tag count changed.
+    ///
+    pub fn remove_all_from_source(
+        &mut self,
+        source: Entity,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) -> bool {
+        let Some(tag_counts) = self.source_tag_counts.remove(&source) else {
+            return false;
+        };
+        let mut updated_any = false;
+        self.current_source = Some(source);
+        for (tag, count) in tag_counts {
+            let count = count.min(self.get_explicit_tag_count(&tag));
+            updated_any |= self
+                .update_tag_map_internal(&tag, -count, tags_manager, commands, entity)
+                .is_changed();
+        }
+        self.current_source = None;
+        updated_any
+    }
+
+    /// Returns the sources currently granting exactly `tag`, with the count each of them grants.
+    pub fn get_tag_sources(&self, tag: &GameplayTag) -> Vec<(Entity, i32)> {
+        self.source_tag_counts
+            .iter()
+            .filter_map(|(source, tag_counts)| tag_counts.get(tag).map(|count| (*source, *count)))
+            .collect()
+    }
+
+    /// Returns the explicit count of `tag` granted by `source`.
+    pub fn get_source_tag_count(&self, source: Entity, tag: &GameplayTag) -> i32 {
+        self.source_tag_counts
+            .get(&source)
+            .and_then(|tag_counts| tag_counts.get(tag))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns all entities that currently grant tags to this container.
+    pub fn get_sources(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.source_tag_counts.keys().copied()
+    }
+
+    /// Returns the explicit count of `tag` that no source granted, e.g. from `update_tag_count`.
+    pub fn get_unattributed_tag_count(&self, tag: &GameplayTag) -> i32 {
+        let source_count: i32 = self
+            .source_tag_counts
+            .values()
+            .filter_map(|tag_counts| tag_counts.get(tag))
+            .sum();
+        self.get_explicit_tag_count(tag) - source_count
+    }
+
+    //来源的计数之和不能超过显式计数；减少时先消耗匿名层数，再按来源实体的顺序扣除
+    fn trim_source_tag_counts(&mut self, tag: &GameplayTag) {
+        let mut excess = -self.get_unattributed_tag_count(tag);
+        if excess <= 0 {
+            return;
+        }
+        let mut sources: Vec<(Entity, i32)> = self.get_tag_sources(tag);
+        sources.sort();
+        for (source, count) in sources {
+            let trimmed = count.min(excess);
+            self.add_source_tag_count(source, tag, -trimmed);
+            excess -= trimmed;
+            if excess <= 0 {
+                break;
+            }
+        }
+    }
+
+    fn add_source_tag_count(&mut self, source: Entity, tag: &GameplayTag, count_delta: i32) {
+        let tag_counts = self.source_tag_counts.entry(source).or_default();
+        let count = tag_counts.entry(tag.clone()).or_insert(0);
+        *count += count_delta;
+        if *count <= 0 {
+            tag_counts.remove(tag);
+            if tag_counts.is_empty() {
+                self.source_tag_counts.remove(&source);
+            }
         }
     }
 
@@ -312,6 +502,27 @@ impl GameplayTagCountContainer {
         }
     }
 
+    ///
+    /// Returns the count of `tag` that is visible to matching, i.e. `get_tag_count` minus the count
+    /// held by explicit tags that are currently inhibited.
+    ///
+    #[inline]
+    pub fn get_visible_tag_count(&self, tag: &GameplayTag) -> i32 {
+        let inhibited_count = self.inhibited_tag_count_map.get(tag).copied().unwrap_or(0);
+        self.get_tag_count(tag) - inhibited_count
+    }
+
+    /// Returns `true` if the explicit `tag` is held by this container but hidden by an inhibition rule.
+    #[inline]
+    pub fn is_tag_inhibited(&self, tag: &GameplayTag) -> bool {
+        self.inhibited_tags.contains(tag)
+    }
+
+    /// Returns the explicit tags that are currently hidden by inhibition rules.
+    pub fn get_inhibited_tags(&self) -> impl Iterator<Item = &GameplayTag> + '_ {
+        self.inhibited_tags.iter()
+    }
+
     ///
     /// Retrieves the count of an explicit tag within the current context.
     ///
@@ -337,6 +548,24 @@ impl GameplayTagCountContainer {
         }
     }
 
+    /// Returns every explicit tag with a positive count, including inhibited ones, with its count.
+    pub fn get_explicit_tag_counts(&self) -> impl Iterator<Item = (&GameplayTag, i32)> + '_ {
+        self.explicit_tag_count_map
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(tag, count)| (tag, *count))
+    }
+
+    ///
+    /// Returns the explicit tags currently held by this container, with their parent tags filled.
+    /// Useful for evaluating a `GameplayTagQuery` or `GameplayTagRequirements` against the container.
+    /// Inhibited tags are left out, see `GameplayTagsManager::add_tag_inhibition`.
+    ///
+    #[inline]
+    pub fn get_explicit_tags(&self) -> &GameplayTagContainer {
+        &self.explicit_tags
+    }
+
     ///
     /// Resets the state of the current object and removes `Observer` components from all entities
     /// that are observing the specified entity.
@@ -373,7 +602,16 @@ impl GameplayTagCountContainer {
     pub fn reset(&mut self, world: &mut World, entity: Entity) {
         self.explicit_tag_count_map.clear();
         self.explicit_tags.reset();
+        self.inhibited_tags.clear();
+        self.inhibited_tag_count_map.clear();
         self.gameplay_tag_count_map.clear();
+        self.stack_decay_timers.clear();
+        self.source_tag_counts.clear();
+        self.batched_changes.clear();
+        self.transaction_depth = 0;
+        self.transaction_changes.clear();
+        self.transaction_refusals.clear();
+        self.pending_notification.clear();
         if let Some(observed_by) = world.get::<ObservedBy>(entity) {
             let observer_entities: Vec<Entity> = observed_by.get().to_vec();
             for observer_entity in observer_entities {
@@ -384,6 +622,210 @@ impl GameplayTagCountContainer {
         }
     }
 
+    ///
+    /// Enables or disables batched change events.
+    ///
+    /// While enabled, tag changes no longer trigger `OnGameplayEffectTagCountChanged` for every tag
+    /// and parent tag. Instead they are collected and sent once per frame as a single
+    /// `GameplayTagsChanged` message holding the net changes. The plugin's own features, such as
+    /// tag markers, registered queries, component and conversion rules, inherited and related tag
+    /// views and tag-scoped observers, keep tracking every change of a batched container.
+    /// Disabling it keeps the changes collected so far; they are still sent at the end of the frame.
+    ///
+    pub fn set_batch_change_events(&mut self, batch_change_events: bool) {
+        self.batch_change_events = batch_change_events;
+    }
+
+    pub fn is_batching_change_events(&self) -> bool {
+        self.batch_change_events
+    }
+
+    ///
+    /// When enabled, `OnGameplayEffectTagCountChanged` is only triggered for the tags that changed
+    /// explicitly, not for their implicit parent tags. The plugin's own features, such as a tag
+    /// marker bound to `Status` or `observe_tag(Status)`, still see changes of `Status.*` tags.
+    ///
+    pub fn set_suppress_implicit_parent_events(&mut self, suppress_implicit_parent_events: bool) {
+        self.suppress_implicit_parent_events = suppress_implicit_parent_events;
+    }
+
+    pub fn is_suppressing_implicit_parent_events(&self) -> bool {
+        self.suppress_implicit_parent_events
+    }
+
+    /// Returns `true` if changes were collected since the last `take_batched_changes`.
+    pub fn has_batched_changes(&self) -> bool {
+        !self.batched_changes.is_empty()
+    }
+
+    ///
+    /// Takes the changes collected in batched mode and folds them into their net effect.
+    ///
+    /// A tag added and removed again in the same batch does not show up at all.
+    ///
+    /// # Returns
+    /// * `None` if nothing changed overall, otherwise the `GameplayTagsChanged` message for `entity`.
+    ///
+    pub fn take_batched_changes(&mut self, entity: Entity) -> Option<GameplayTagsChanged> {
+        let mut changes = GameplayTagsChanged {
+            entity,
+            added: Vec::new(),
+            removed: Vec::new(),
+            count_changes: Vec::new(),
+        };
+        let batched_changes = std::mem::take(&mut self.batched_changes);
+        for (tag, pending) in batched_changes {
+            let PendingTagChange {
+                old_count,
+                old_visible_count,
+                ..
+            } = pending;
+            let new_count = self.get_tag_count(&tag);
+            let new_visible_count = self.get_visible_tag_count(&tag);
+            if old_visible_count <= 0 && new_visible_count > 0 {
+                changes.added.push(tag.clone());
+            } else if old_visible_count > 0 && new_visible_count <= 0 {
+                changes.removed.push(tag.clone());
+            }
+            if old_count != new_count {
+                changes.count_changes.push(GameplayTagCountChange {
+                    tag,
+                    old_count,
+                    new_count,
+                });
+            }
+        }
+        if changes.added.is_empty()
+            && changes.removed.is_empty()
+            && changes.count_changes.is_empty()
+        {
+            return None;
+        }
+        changes.added.sort();
+        changes.removed.sort();
+        changes.count_changes.sort_by(|a, b| a.tag.cmp(&b.tag));
+        Some(changes)
+    }
+
+    ///
+    /// Starts a transaction. Until the matching `commit`, tag changes trigger no
+    /// `OnGameplayEffectTagCountChanged` events, refused additions trigger no `GameplayTagBlocked`
+    /// or `GameplayTagStackLimitReached` events, and parent tags of removed tags are not rebuilt.
+    /// Transactions can be nested; only the outermost `commit` finishes them.
+    ///
+    /// Prefer `modify`, which cannot forget the `commit`.
+    ///
+    pub fn begin_batch(&mut self) {
+        self.transaction_depth += 1;
+    }
+
+    /// Returns `true` while a transaction started with `begin_batch` is open.
+    pub fn is_in_batch(&self) -> bool {
+        self.transaction_depth > 0
+    }
+
+    ///
+    /// Finishes the transaction started with `begin_batch`.
+    ///
+    /// `GameplayTagBlocked` and `GameplayTagStackLimitReached` events of the transaction are triggered
+    /// first, in the order they happened. Parent tags are rebuilt once, then one event per event
+    /// type is triggered for every tag whose count or presence differs from before the transaction.
+    /// Within the transaction, removals come before additions, and child tags before their parents
+    /// in the tag hierarchy. Tags that were added and removed again inside the transaction trigger
+    /// nothing. If the container batches its change events, the net changes are left for the
+    /// frame's `GameplayTagsChanged` message instead.
+    ///
+    pub fn commit(
+        &mut self,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        if self.transaction_depth == 0 {
+            warn!(
+                "实体 {} 的标签容器在没有开始事务的情况下调用了commit",
+                entity
+            );
+            return;
+        }
+        self.transaction_depth -= 1;
+        if self.transaction_depth > 0 {
+            return;
+        }
+        for refusal in std::mem::take(&mut self.transaction_refusals) {
+            refusal.trigger(commands);
+        }
+        if self.transaction_changes.is_empty() {
+            return;
+        }
+        self.explicit_tags.fill_parent_tags(tags_manager);
+        let mut net_changes: Vec<(GameplayTag, PendingTagChange, bool)> =
+            std::mem::take(&mut self.transaction_changes)
+                .into_iter()
+                .map(|(tag, pending)| {
+                    let is_addition = (self.get_visible_tag_count(&tag), self.get_tag_count(&tag))
+                        > (pending.old_visible_count, pending.old_count);
+                    (tag, pending, is_addition)
+                })
+                .collect();
+        //先移除后添加；父标签数量越多层级越深，所以子标签一定排在它的父标签之前；其余按标签排序
+        net_changes.sort_by_cached_key(|(tag, _, is_addition)| {
+            let num_parents = tags_manager
+                .get_single_tag_container(tag)
+                .map_or(0, |complete_container| complete_container.get_parent_tags().len());
+            (*is_addition, std::cmp::Reverse(num_parents), tag.clone())
+        });
+        for (tag, pending, _) in net_changes {
+            self.emit_tag_change(&tag, pending, commands, entity);
+        }
+        self.flush_notification(commands, entity);
+    }
+
+    //结束当前事务但不触发任何事件，最外层事务结束时丢弃记录的变化
+    fn abandon_transaction(&mut self, tags_manager: &GameplayTagsManager) {
+        self.transaction_depth = self.transaction_depth.saturating_sub(1);
+        if self.transaction_depth == 0 {
+            self.transaction_changes.clear();
+            self.transaction_refusals.clear();
+            self.explicit_tags.fill_parent_tags(tags_manager);
+        }
+    }
+
+    ///
+    /// Runs `modify` as a transaction, see `begin_batch` and `commit`.
+    ///
+    /// The transaction is only committed when `modify` returns. If it panics, the transaction is
+    /// abandoned instead: the changes made so far stay in the container, but no events are triggered
+    /// for them.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// count_container.modify(&tags_manager, &mut commands, entity, |tx| {
+    ///     tx.add_tag(&GameplayTag::new("Status.Burning"), 1);
+    ///     tx.add_tag(&GameplayTag::new("Status.Slowed"), 2);
+    ///     tx.remove_tag(&GameplayTag::new("Status.Wet"), 1);
+    /// });
+    /// ```
+    ///
+    pub fn modify<R>(
+        &mut self,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+        modify: impl FnOnce(&mut GameplayTagTransaction) -> R,
+    ) -> R {
+        self.begin_batch();
+        let mut guard = GameplayTagTransactionGuard {
+            transaction: GameplayTagTransaction {
+                count_container: self,
+                tags_manager,
+                commands,
+                entity,
+            },
+        };
+        modify(&mut guard.transaction)
+    }
+
     ///
     /// Fills the parent tags for the explicit tags associated with the current object.
     ///
@@ -408,68 +850,486 @@ impl GameplayTagCountContainer {
     /// self.fill_parent_tags(&tags_manager);
     /// ```
     ///
-    pub fn fill_parent_tags(&mut self, tags_manager: &Res<GameplayTagsManager>) {
+    pub fn fill_parent_tags(&mut self, tags_manager: &GameplayTagsManager) {
         self.explicit_tags.fill_parent_tags(tags_manager);
     }
 
+    ///
+    /// Advances the stack decay timers of tags whose `GameplayTagStackingPolicy` has a
+    /// `stack_decay_period`, removing one stack per elapsed period.
+    /// This is called by the plugin every frame.
+    ///
+    pub fn tick_stack_decay(
+        &mut self,
+        delta: Duration,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        let mut decayed_stacks = Vec::new();
+        for (tag, elapsed) in self.stack_decay_timers.iter_mut() {
+            let Some(period) = tags_manager
+                .get_stacking_policy(tag)
+                .and_then(|policy| policy.get_stack_decay_period())
+            else {
+                continue;
+            };
+            *elapsed += delta;
+            let mut stacks = 0;
+            while *elapsed >= period {
+                *elapsed -= period;
+                stacks += 1;
+            }
+            if stacks > 0 {
+                decayed_stacks.push((tag.clone(), stacks));
+            }
+        }
+        //按标签排序，保证事件顺序稳定
+        decayed_stacks.sort();
+        for (tag, stacks) in decayed_stacks {
+            self.update_tag_map_internal(&tag, -stacks, tags_manager, commands, entity);
+        }
+    }
+
+    pub(crate) fn has_stack_decay_timers(&self) -> bool {
+        !self.stack_decay_timers.is_empty()
+    }
+
     fn update_tag_map_internal(
         &mut self,
         tag: &GameplayTag,
         count_delta: i32,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
         commands: &mut Commands,
         entity: Entity,
-    ) -> bool {
-        if !self.update_explicit_tags(tag, count_delta, false, tags_manager) {
-            false
-        } else {
-            self.gather_tag_change_delegates(tag, count_delta, tags_manager, commands, entity)
-        }
+    ) -> GameplayTagCountUpdateResult {
+        self.update_tag_map(tag, count_delta, false, tags_manager, commands, entity)
     }
 
     fn update_tag_map_deferred_parent_removal_internal(
         &mut self,
         tag: &GameplayTag,
         count_delta: i32,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
         commands: &mut Commands,
         entity: Entity,
+    ) -> GameplayTagCountUpdateResult {
+        self.update_tag_map(tag, count_delta, true, tags_manager, commands, entity)
+    }
+
+    fn update_tag_map(
+        &mut self,
+        tag: &GameplayTag,
+        count_delta: i32,
+        defer_parent_tags_on_remove: bool,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) -> GameplayTagCountUpdateResult {
+        if count_delta > 0
+            && let Some(blocked_by) = self.find_blocking_tag(tag, tags_manager)
+        {
+            self.notify_refusal(
+                GameplayTagRefusal::Blocked(GameplayTagBlocked {
+                    entity,
+                    tag: tag.clone(),
+                    blocked_by,
+                    requested_delta: count_delta,
+                }),
+                commands,
+            );
+            return GameplayTagCountUpdateResult::Blocked;
+        }
+
+        let is_new_tag = self.get_explicit_tag_count(tag) <= 0;
+        //事务中统一在commit时重建父标签
+        let defer_parent_tags_on_remove = defer_parent_tags_on_remove || self.transaction_depth > 0;
+        let result =
+            self.update_explicit_tags(tag, count_delta, defer_parent_tags_on_remove, tags_manager);
+        if result.get_applied_delta() < 0 {
+            self.trim_source_tag_counts(tag);
+        }
+        self.apply_explicit_update_result(tag, count_delta, result, tags_manager, commands, entity);
+        //先添加再移除互斥标签，避免共同父标签的计数中途归零
+        if is_new_tag && result.is_changed() && tags_manager.has_exclusive_groups() {
+            self.remove_exclusive_siblings(tag, tags_manager, commands, entity);
+        }
+        self.flush_notification(commands, entity);
+        result
+    }
+
+    fn remove_exclusive_siblings(
+        &mut self,
+        tag: &GameplayTag,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        let siblings: Vec<(GameplayTag, i32)> = self
+            .explicit_tag_count_map
+            .iter()
+            .filter(|(other, count)| **count > 0 && tags_manager.are_exclusive_siblings(tag, other))
+            .map(|(other, count)| (other.clone(), *count))
+            .collect();
+        for (sibling, count) in siblings {
+            //移除事件归属于原本授予该标签的来源，而不是触发互斥的来源
+            let owner = self
+                .source_tag_counts
+                .iter()
+                .filter(|(_, tag_counts)| tag_counts.contains_key(&sibling))
+                .map(|(source, _)| *source)
+                .min();
+            //互斥移除的标签不再属于任何来源
+            for tag_counts in self.source_tag_counts.values_mut() {
+                tag_counts.remove(&sibling);
+            }
+            self.source_tag_counts
+                .retain(|_, tag_counts| !tag_counts.is_empty());
+            let granting_source = std::mem::replace(&mut self.current_source, owner);
+            self.update_tag_map_internal(&sibling, -count, tags_manager, commands, entity);
+            self.current_source = granting_source;
+        }
+    }
+
+    ///
+    /// Returns the present tag that blocks adding `tag`, if any. A block rule applies to the blocked
+    /// tag and all of its children, and its blocker counts as present if any of its children is.
+    ///
+    fn find_blocking_tag(
+        &self,
+        tag: &GameplayTag,
+        tags_manager: &GameplayTagsManager,
+    ) -> Option<GameplayTag> {
+        tags_manager
+            .get_tag_block_rules()
+            .find(|(blocker, blocked_tags)| {
+                self.has_rule_tag(blocker, true)
+                    && blocked_tags
+                        .iter()
+                        .any(|blocked_tag| matches_rule_tag(tag, blocked_tag, tags_manager))
+            })
+            .map(|(blocker, _)| blocker.clone())
+    }
+
+    ///
+    /// Returns `true` if the rule tag `rule_tag` or one of its children is present. With
+    /// `visible_only` set, explicit tags hidden by inhibition are ignored.
+    ///
+    fn has_rule_tag(&self, rule_tag: &GameplayTag, visible_only: bool) -> bool {
+        let count = if visible_only {
+            self.get_visible_tag_count(rule_tag)
+        } else {
+            self.get_tag_count(rule_tag)
+        };
+        //未注册的标签不会计入父标签的计数，只能按名字在显式标签中查找
+        count > 0
+            || self
+                .explicit_tag_count_map
+                .iter()
+                .any(|(explicit_tag, explicit_count)| {
+                    *explicit_count > 0
+                        && !(visible_only && self.inhibited_tags.contains(explicit_tag))
+                        && explicit_tag.matches_tag_name(rule_tag)
+                })
+    }
+
+    fn apply_explicit_update_result(
+        &mut self,
+        tag: &GameplayTag,
+        count_delta: i32,
+        result: GameplayTagCountUpdateResult,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        if let GameplayTagCountUpdateResult::Saturated { .. } | GameplayTagCountUpdateResult::Rejected = result
+            && let Some(policy) = tags_manager.get_stacking_policy(tag)
+            && let Some(max_stack_count) = policy.max_stack_count
+        {
+            self.notify_refusal(
+                GameplayTagRefusal::StackLimitReached(GameplayTagStackLimitReached {
+                    entity,
+                    tag: tag.clone(),
+                    requested_delta: count_delta,
+                    applied_delta: result.get_applied_delta(),
+                    max_stack_count,
+                    overflow_policy: policy.overflow_policy,
+                }),
+                commands,
+            );
+        }
+
+        let applied_delta = result.get_applied_delta();
+        if applied_delta != 0 {
+            let explicit_count = self.get_explicit_tag_count(tag);
+            //未注册的标签没有层级计数，需要单独判断显式标签是否新增或完全移除
+            let explicit_presence_changed = explicit_count <= 0 || explicit_count == applied_delta;
+            let presence_changed =
+                self.gather_tag_change_delegates(tag, applied_delta, tags_manager, commands, entity)
+                    || explicit_presence_changed;
+            if self.get_explicit_tag_count(tag) <= 0 {
+                self.inhibited_tags.remove(tag);
+            }
+            //某个标签新增或完全移除时，抑制关系可能随之改变
+            if presence_changed && tags_manager.has_tag_inhibitions() {
+                self.refresh_inhibited_tags(tags_manager, commands, entity);
+            }
+        }
+    }
+
+    ///
+    /// Returns `true` if an inhibition rule hides `tag`, i.e. its inhibitor or one of the
+    /// inhibitor's children is present and `tag` matches one of the inhibited tags. Rules are
+    /// matched the same way as block rules, see `find_blocking_tag`.
+    ///
+    fn is_inhibited_by_rules(
+        &self,
+        tag: &GameplayTag,
+        tags_manager: &GameplayTagsManager,
     ) -> bool {
-        if !self.update_explicit_tags(tag, count_delta, true, tags_manager) {
-            false
+        tags_manager
+            .get_tag_inhibition_rules()
+            .any(|(inhibitor, inhibited_tags)| {
+                //抑制者本身按总计数判断，避免相互抑制时来回切换
+                self.has_rule_tag(inhibitor, false)
+                    && inhibited_tags
+                        .iter()
+                        .any(|inhibited_tag| matches_rule_tag(tag, inhibited_tag, tags_manager))
+            })
+    }
+
+    fn refresh_inhibited_tags(
+        &mut self,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        let changed_tags: Vec<(GameplayTag, bool)> = self
+            .explicit_tag_count_map
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .filter_map(|(tag, _)| {
+                let inhibited = self.is_inhibited_by_rules(tag, tags_manager);
+                (inhibited != self.inhibited_tags.contains(tag)).then(|| (tag.clone(), inhibited))
+            })
+            .collect();
+        for (tag, inhibited) in changed_tags {
+            self.set_tag_inhibited(&tag, inhibited, tags_manager, commands, entity);
+        }
+    }
+
+    ///
+    /// Hides or reveals the explicit `tag`, moving its count between the visible and inhibited counts
+    /// and triggering `NewOrRemoved` events for every tag whose visibility changed.
+    ///
+    fn set_tag_inhibited(
+        &mut self,
+        tag: &GameplayTag,
+        inhibited: bool,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        let count = self.get_explicit_tag_count(tag);
+        let inhibited_delta = if inhibited {
+            self.inhibited_tags.insert(tag.clone());
+            self.explicit_tags.remove_tag(tag, false, tags_manager);
+            count
+        } else {
+            self.inhibited_tags.remove(tag);
+            self.explicit_tags.add_tag_non_exclusive(tag.clone(), tags_manager);
+            -count
+        };
+
+        let tag_inhibited = tag;
+        let tag_and_parents_container = tags_manager.request_gameplay_tag_parents(tag);
+        for tag in tag_and_parents_container.gameplay_tags.into_iter() {
+            let old_count = self.get_tag_count(&tag);
+            let old_visible_count = self.get_visible_tag_count(&tag);
+            self.add_inhibited_tag_count(&tag, inhibited_delta);
+            self.notify_tag_change(
+                &tag,
+                tag_inhibited,
+                old_count,
+                old_visible_count,
+                commands,
+                entity,
+            );
+        }
+    }
+
+    //在标签计数更新之后调用。事务中只记录变化前的计数，commit时再按净变化通知
+    fn notify_refusal(&mut self, refusal: GameplayTagRefusal, commands: &mut Commands) {
+        if self.transaction_depth > 0 {
+            self.transaction_refusals.push(refusal);
         } else {
-            self.gather_tag_change_delegates(tag, count_delta, tags_manager, commands, entity)
+            refusal.trigger(commands);
+        }
+    }
+
+    fn notify_tag_change(
+        &mut self,
+        tag: &GameplayTag,
+        cause_tag: &GameplayTag,
+        old_count: i32,
+        old_visible_count: i32,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        let change = PendingTagChange {
+            old_count,
+            old_visible_count,
+            cause_tag: cause_tag.clone(),
+            source: self.current_source,
+        };
+        if self.transaction_depth > 0 {
+            Self::record_pending_change(&mut self.transaction_changes, tag, change);
+        } else {
+            self.emit_tag_change(tag, change, commands, entity);
+        }
+    }
+
+    //按变化前后的计数触发事件，批量模式下同时记录下来，留给每帧的GameplayTagsChanged消息
+    fn emit_tag_change(
+        &mut self,
+        tag: &GameplayTag,
+        change: PendingTagChange,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        let new_count = self.get_tag_count(tag);
+        let new_visible_count = self.get_visible_tag_count(tag);
+        if (change.old_visible_count > 0) != (new_visible_count > 0) {
+            //OnNewOrRemove
+            self.trigger_tag_change(
+                tag,
+                &change.cause_tag,
+                GameplayTagEventType::NewOrRemoved,
+                change.old_visible_count,
+                new_visible_count,
+                change.source,
+                commands,
+                entity,
+            );
+        }
+        if change.old_count != new_count {
+            //OnAnyChange
+            self.trigger_tag_change(
+                tag,
+                &change.cause_tag,
+                GameplayTagEventType::AnyCountChanged,
+                change.old_count,
+                new_count,
+                change.source,
+                commands,
+                entity,
+            );
+        }
+        if self.batch_change_events {
+            Self::record_pending_change(&mut self.batched_changes, tag, change);
+        }
+    }
+
+    //只记录第一次变化前的计数，提交或发送时与当前计数比较得到净变化
+    fn record_pending_change(
+        pending_changes: &mut HashMap<GameplayTag, PendingTagChange>,
+        tag: &GameplayTag,
+        change: PendingTagChange,
+    ) {
+        match pending_changes.entry(tag.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(change);
+            }
+            //标签自身发生过显式变化时，以显式变化为准
+            Entry::Occupied(mut entry) => {
+                if *tag == change.cause_tag {
+                    let pending = entry.get_mut();
+                    pending.cause_tag = change.cause_tag;
+                    pending.source = change.source;
+                }
+            }
         }
     }
+
+    fn add_inhibited_tag_count(&mut self, tag: &GameplayTag, count_delta: i32) {
+        let count = self.inhibited_tag_count_map.entry(tag.clone()).or_insert(0);
+        *count += count_delta;
+        if *count <= 0 {
+            self.inhibited_tag_count_map.remove(tag);
+        }
+    }
+
     fn update_explicit_tags(
         &mut self,
         tag: &GameplayTag,
         count_delta: i32,
         defer_parent_tags_on_remove: bool,
-        tags_manager: &Res<GameplayTagsManager>,
-    ) -> bool {
-        let tag_already_exists = self.explicit_tags.has_tag_exact(&tag);
-        if !tag_already_exists {
-            if count_delta > 0 {
-                self.explicit_tags.add_tag(tag.clone(), tags_manager);
-            } else {
-                if self.explicit_tags.has_tag(&tag) {
-                    warn!(
-                        "试图从标记计数容器中删除标记：{}, 但该标记不在容器中！",
-                        tag.get_tag_name()
-                    );
+        tags_manager: &GameplayTagsManager,
+    ) -> GameplayTagCountUpdateResult {
+        let tag_already_exists = self.get_explicit_tag_count(tag) > 0;
+        if !tag_already_exists && count_delta <= 0 {
+            if self.explicit_tags.has_tag(tag) {
+                warn!(
+                    "试图从标记计数容器中删除标记：{}, 但该标记不在容器中！",
+                    tag.get_tag_name()
+                );
+            }
+            return GameplayTagCountUpdateResult::Unchanged;
+        }
+
+        let existing_count = self.get_explicit_tag_count(tag);
+        let mut new_count = (existing_count + count_delta).max(0);
+        let mut saturated = false;
+        let stacking_policy = tags_manager.get_stacking_policy(tag);
+        if count_delta > 0
+            && let Some(policy) = stacking_policy
+            && let Some(max_stack_count) = policy.max_stack_count
+            && new_count > max_stack_count
+        {
+            match policy.overflow_policy {
+                GameplayTagStackOverflowPolicy::Reject => {
+                    return GameplayTagCountUpdateResult::Rejected;
+                }
+                GameplayTagStackOverflowPolicy::Saturate => {
+                    new_count = max_stack_count.max(existing_count);
+                    saturated = true;
                 }
-                return false;
             }
         }
 
-        let existing_count = self.explicit_tag_count_map.entry(tag.clone()).or_insert(0);
-        *existing_count = (*existing_count + count_delta).max(0);
-        if *existing_count <= 0 {
+        let applied_delta = new_count - existing_count;
+        if applied_delta == 0 {
+            return if saturated {
+                GameplayTagCountUpdateResult::Saturated { applied_delta }
+            } else {
+                GameplayTagCountUpdateResult::Unchanged
+            };
+        }
+
+        if !tag_already_exists {
+            if tags_manager.has_tag_inhibitions() && self.is_inhibited_by_rules(tag, tags_manager) {
+                self.inhibited_tags.insert(tag.clone());
+            } else {
+                self.explicit_tags.add_tag_non_exclusive(tag.clone(), tags_manager);
+            }
+        }
+        self.explicit_tag_count_map.insert(tag.clone(), new_count);
+        if new_count <= 0 {
             self.explicit_tags
-                .remove_tag(&tag, defer_parent_tags_on_remove, tags_manager);
+                .remove_tag(tag, defer_parent_tags_on_remove, tags_manager);
+            self.stack_decay_timers.remove(tag);
+        } else if applied_delta > 0
+            && stacking_policy.is_some_and(|policy| policy.get_stack_decay_period().is_some())
+        {
+            //每次叠加都重新开始衰减计时
+            self.stack_decay_timers.insert(tag.clone(), Duration::ZERO);
+        }
+
+        if saturated {
+            GameplayTagCountUpdateResult::Saturated { applied_delta }
+        } else {
+            GameplayTagCountUpdateResult::Updated { applied_delta }
         }
-        true
     }
 
     ///
@@ -479,7 +1339,7 @@ impl GameplayTagCountContainer {
     /// # Arguments
     /// * `tag` - A reference to the `GameplayTag` that is being updated.
     /// * `count_delta` - The change in count for the specified tag. Positive values increase the count, negative values decrease it.
-    /// * `tags_manager` - A resource containing the `GameplayTagsManager` which provides access to tag information including parent tags.
+    /// * `tags_manager` - A reference to the `GameplayTagsManager` which provides access to tag information including parent tags.
     /// * `commands` - A mutable reference to `Commands` used to trigger events.
     /// * `entity` - The `Entity` associated with the tag changes.
     ///
@@ -488,49 +1348,262 @@ impl GameplayTagCountContainer {
     ///
     /// This function updates the internal count of the specified tag and its parent tags, checks for significant changes (when a tag's count goes from non-zero to zero or vice versa),
     /// and triggers appropriate events (`OnGameplayEffectTagCountChanged`) with the type of event being either `NewOrRemoved` or `AnyCountChanged` based on the nature of the change.
+    /// `NewOrRemoved` follows the visible count, so it is not triggered while `tag` is inhibited.
     ///
     fn gather_tag_change_delegates(
         &mut self,
         tag: &GameplayTag,
         count_delta: i32,
-        tags_manager: &Res<GameplayTagsManager>,
+        tags_manager: &GameplayTagsManager,
         commands: &mut Commands,
         entity: Entity,
     ) -> bool {
+        let cause_tag = tag;
         let tag_and_parents_container = tags_manager.request_gameplay_tag_parents(tag);
+        let is_inhibited = self.inhibited_tags.contains(tag);
         let mut created_significant_change = false;
 
         for tag in tag_and_parents_container.gameplay_tags.into_iter() {
+            let old_visible_count = self.get_visible_tag_count(&tag);
             let tag_count = self.gameplay_tag_count_map.entry(tag.clone()).or_insert(0);
             let old_count = *tag_count;
             let new_count = (old_count + count_delta).max(0);
             *tag_count = new_count;
+            if is_inhibited {
+                self.add_inhibited_tag_count(&tag, new_count - old_count);
+            }
             //如果发生重大变化（新增或完全删除），触发相关事件
             let significant_change = old_count == 0 || new_count == 0;
             created_significant_change |= significant_change;
-            if significant_change {
-                //OnNewOrRemove
-                commands.trigger(OnGameplayEffectTagCountChanged {
-                    entity,
-                    tag: tag.clone(),
-                    new_count,
-                    event_type: GameplayTagEventType::NewOrRemoved,
-                });
-            }
-            //OnAnyChange
-            commands.trigger(OnGameplayEffectTagCountChanged {
+            self.notify_tag_change(
+                &tag,
+                cause_tag,
+                old_count,
+                old_visible_count,
+                commands,
                 entity,
-                tag: tag.clone(),
-                new_count,
-                event_type: GameplayTagEventType::AnyCountChanged,
-            });
+            );
         }
 
         created_significant_change
     }
+
+    #[allow(clippy::too_many_arguments)]
+    fn trigger_tag_change(
+        &mut self,
+        tag: &GameplayTag,
+        cause_tag: &GameplayTag,
+        event_type: GameplayTagEventType,
+        old_count: i32,
+        new_count: i32,
+        source: Option<Entity>,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        let is_explicit = tag == cause_tag;
+        let change = OnGameplayEffectTagCountChanged {
+            entity,
+            tag: tag.clone(),
+            new_count,
+            event_type,
+            old_count,
+            delta: new_count - old_count,
+            is_explicit,
+            cause_tag: cause_tag.clone(),
+            source,
+        };
+        if !self.batch_change_events && (is_explicit || !self.suppress_implicit_parent_events) {
+            commands.trigger(change.clone());
+        }
+        //插件自身的功能始终收到所有变化，批量模式只影响对外的事件
+        self.pending_notification.push(change);
+    }
+
+    //每次更新只触发一次内部通知，插件内部的观察者不必为每个标签及其父标签各运行一次
+    fn flush_notification(&mut self, commands: &mut Commands, entity: Entity) {
+        if !self.pending_notification.is_empty() {
+            commands.trigger(GameplayTagCountNotification {
+                entity,
+                changes: std::mem::take(&mut self.pending_notification),
+            });
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+//屏蔽和抑制规则作用于规则标签及其子标签，未注册的标签按名字匹配
+fn matches_rule_tag(
+    tag: &GameplayTag,
+    rule_tag: &GameplayTag,
+    tags_manager: &GameplayTagsManager,
+) -> bool {
+    tag.matches_tag(rule_tag, tags_manager) || tag.matches_tag_name(rule_tag)
+}
+
+//被拒绝的添加操作对应的事件
+#[derive(Debug, Clone)]
+enum GameplayTagRefusal {
+    Blocked(GameplayTagBlocked),
+    StackLimitReached(GameplayTagStackLimitReached),
+}
+
+impl GameplayTagRefusal {
+    fn trigger(self, commands: &mut Commands) {
+        match self {
+            GameplayTagRefusal::Blocked(event) => commands.trigger(event),
+            GameplayTagRefusal::StackLimitReached(event) => commands.trigger(event),
+        }
+    }
+}
+
+//批量模式或事务中某个标签第一次变化前的状态
+#[derive(Debug, Clone)]
+struct PendingTagChange {
+    old_count: i32,
+    old_visible_count: i32,
+    cause_tag: GameplayTag,
+    source: Option<Entity>,
+}
+
+///
+/// The tag operations available inside `GameplayTagCountContainer::modify`. Every change is applied
+/// immediately, but its events are only triggered once the transaction is committed.
+///
+pub struct GameplayTagTransaction<'a, 'w, 's> {
+    count_container: &'a mut GameplayTagCountContainer,
+    tags_manager: &'a GameplayTagsManager,
+    commands: &'a mut Commands<'w, 's>,
+    entity: Entity,
+}
+
+impl GameplayTagTransaction<'_, '_, '_> {
+    pub fn add_tag(&mut self, tag: &GameplayTag, count: i32) -> GameplayTagCountUpdateResult {
+        self.update_tag_count(tag, count)
+    }
+
+    pub fn remove_tag(&mut self, tag: &GameplayTag, count: i32) -> GameplayTagCountUpdateResult {
+        self.update_tag_count(tag, -count)
+    }
+
+    pub fn update_tag_count(
+        &mut self,
+        tag: &GameplayTag,
+        count_delta: i32,
+    ) -> GameplayTagCountUpdateResult {
+        self.count_container.update_tag_count(
+            tag,
+            count_delta,
+            self.tags_manager,
+            self.commands,
+            self.entity,
+        )
+    }
+
+    pub fn set_tag_count(
+        &mut self,
+        tag: &GameplayTag,
+        new_count: i32,
+    ) -> GameplayTagCountUpdateResult {
+        self.count_container.set_tag_count(
+            tag,
+            new_count,
+            self.tags_manager,
+            self.commands,
+            self.entity,
+        )
+    }
+
+    pub fn update_tag_container_count(
+        &mut self,
+        container: &GameplayTagContainer,
+        count_delta: i32,
+    ) {
+        self.count_container.update_tag_container_count(
+            container,
+            count_delta,
+            self.tags_manager,
+            self.commands,
+            self.entity,
+        );
+    }
+
+    pub fn update_tag_count_from_source(
+        &mut self,
+        tag: &GameplayTag,
+        count_delta: i32,
+        source: Entity,
+    ) -> GameplayTagCountUpdateResult {
+        self.count_container.update_tag_count_from_source(
+            tag,
+            count_delta,
+            source,
+            self.tags_manager,
+            self.commands,
+            self.entity,
+        )
+    }
+
+    /// Read access to the container, reflecting the changes made so far in the transaction.
+    pub fn get_count_container(&self) -> &GameplayTagCountContainer {
+        self.count_container
+    }
+}
+
+//正常返回时提交事务；修改闭包panic时只放弃事务，不发布修改了一半的状态
+struct GameplayTagTransactionGuard<'a, 'w, 's> {
+    transaction: GameplayTagTransaction<'a, 'w, 's>,
+}
+
+impl Drop for GameplayTagTransactionGuard<'_, '_, '_> {
+    fn drop(&mut self) {
+        let transaction = &mut self.transaction;
+        if std::thread::panicking() {
+            transaction
+                .count_container
+                .abandon_transaction(transaction.tags_manager);
+        } else {
+            transaction.count_container.commit(
+                transaction.tags_manager,
+                transaction.commands,
+                transaction.entity,
+            );
+        }
+    }
+}
+
+/// Outcome of a single tag count update on a `GameplayTagCountContainer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameplayTagCountUpdateResult {
+    /// Nothing changed, e.g. the delta was 0 or the tag to remove was not in the container.
+    Unchanged,
+    /// The delta was applied. `applied_delta` differs from the requested delta only when a
+    /// removal was clamped at 0.
+    Updated { applied_delta: i32 },
+    /// The addition was cut down to the tag's `max_stack_count`.
+    Saturated { applied_delta: i32 },
+    /// The addition was rejected because it would exceed the tag's `max_stack_count`.
+    Rejected,
+    /// The addition was rejected because a tag blocking it is present.
+    Blocked,
+}
+
+impl GameplayTagCountUpdateResult {
+    /// Returns `true` if the explicit count of the tag changed.
+    pub fn is_changed(&self) -> bool {
+        self.get_applied_delta() != 0
+    }
+
+    pub fn get_applied_delta(&self) -> i32 {
+        match self {
+            GameplayTagCountUpdateResult::Updated { applied_delta }
+            | GameplayTagCountUpdateResult::Saturated { applied_delta } => *applied_delta,
+            GameplayTagCountUpdateResult::Unchanged
+            | GameplayTagCountUpdateResult::Rejected
+            | GameplayTagCountUpdateResult::Blocked => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameplayTagEventType {
     /** Event only happens when tag is new or completely removed */
     NewOrRemoved,
@@ -547,11 +1620,263 @@ pub enum GameplayTagEventType {
 ///
 /// 使用函数名创建观察者
 /// world.spawn_empty().observe(on_tag_changed);
-#[derive(EntityEvent, Debug)]
+#[derive(EntityEvent, Debug, Clone)]
 #[allow(dead_code)]
 pub struct OnGameplayEffectTagCountChanged {
     pub entity: Entity,
     pub tag: GameplayTag,
     pub new_count: i32,
     pub event_type: GameplayTagEventType,
+    /// The count before the change. For `NewOrRemoved` events both counts are visible counts.
+    pub old_count: i32,
+    /// `new_count - old_count`.
+    pub delta: i32,
+    /// `false` if `tag` only changed as an implicit parent of `cause_tag`.
+    pub is_explicit: bool,
+    /// The explicit tag whose change caused this event.
+    pub cause_tag: GameplayTag,
+    /// The source entity the change is attributed to, see `update_tag_count_from_source`.
+    pub source: Option<Entity>,
+}
+
+//插件内部功能（标记组件、查询成员、各类规则和视图等）观察的标签变化，不受批量模式影响。
+//一次更新或一次事务提交的所有变化合并在一个通知中，顺序与对外事件相同
+#[derive(EntityEvent, Debug)]
+pub(crate) struct GameplayTagCountNotification {
+    pub(crate) entity: Entity,
+    pub(crate) changes: Vec<OnGameplayEffectTagCountChanged>,
+}
+
+impl GameplayTagCountNotification {
+    //标签新增或完全移除，只有这种变化才可能改变匹配结果
+    pub(crate) fn presence_changes(
+        &self,
+    ) -> impl Iterator<Item = &OnGameplayEffectTagCountChanged> + '_ {
+        self.changes
+            .iter()
+            .filter(|change| change.event_type == GameplayTagEventType::NewOrRemoved)
+    }
+
+    pub(crate) fn has_presence_changes(&self) -> bool {
+        self.presence_changes().next().is_some()
+    }
+}
+
+/// Net count change of one tag, including implicit parent tags, within a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameplayTagCountChange {
+    pub tag: GameplayTag,
+    pub old_count: i32,
+    pub new_count: i32,
+}
+
+///
+/// Net tag changes of an entity whose `GameplayTagCountContainer` batches its change events,
+/// sent once per frame. `added` and `removed` list the tags, including parent tags, that became
+/// visible or stopped being visible, like `NewOrRemoved` events would.
+///
+/// # Examples
+/// ```ignore
+/// fn on_tags_changed(mut messages: MessageReader<GameplayTagsChanged>) {
+///     for changes in messages.read() {
+///         info!("{:?} 新增 {:?} 移除 {:?}", changes.entity, changes.added, changes.removed);
+///     }
+/// }
+/// ```
+///
+#[derive(Message, Debug, Clone)]
+pub struct GameplayTagsChanged {
+    pub entity: Entity,
+    pub added: Vec<GameplayTag>,
+    pub removed: Vec<GameplayTag>,
+    pub count_changes: Vec<GameplayTagCountChange>,
+}
+
+pub(crate) fn send_batched_gameplay_tag_changes(
+    mut query: Query<(Entity, &mut GameplayTagCountContainer)>,
+    mut messages: MessageWriter<GameplayTagsChanged>,
+) {
+    for (entity, mut count_container) in query.iter_mut() {
+        if !count_container.has_batched_changes() {
+            continue;
+        }
+        //取出批量记录不算标签变化，不触发变更检测
+        if let Some(changes) = count_container
+            .bypass_change_detection()
+            .take_batched_changes(entity)
+        {
+            messages.write(changes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{
+        observer::On,
+        resource::Resource,
+        world::{DeferredWorld, World},
+    };
+
+    use super::*;
+    use crate::gameplay_tag_commands::GameplayTagWorldExt;
+
+    #[derive(Resource, Default)]
+    struct NotificationSizes(Vec<usize>);
+
+    #[derive(Resource, Default)]
+    struct SeenChanges(Vec<OnGameplayEffectTagCountChanged>);
+
+    fn setup_world() -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        (world, entity)
+    }
+
+    fn update_tag_count(
+        world: &mut World,
+        entity: Entity,
+        tag_name: &str,
+        count_delta: i32,
+    ) -> GameplayTagCountUpdateResult {
+        world
+            .modify_gameplay_tags(entity, |tx| {
+                tx.update_tag_count(&GameplayTag::new(tag_name), count_delta)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn unregistered_child_of_blocker_blocks_tag() {
+        let (mut world, entity) = setup_world();
+        world
+            .resource_mut::<GameplayTagsManager>()
+            .add_tag_block(GameplayTag::new("X.Immune"), GameplayTag::new("Status.Burning"));
+        update_tag_count(&mut world, entity, "X.Immune.Fire", 1);
+
+        let result = update_tag_count(&mut world, entity, "Status.Burning", 1);
+        assert_eq!(result, GameplayTagCountUpdateResult::Blocked);
+
+        update_tag_count(&mut world, entity, "X.Immune.Fire", -1);
+        let result = update_tag_count(&mut world, entity, "Status.Burning", 1);
+        assert_eq!(result, GameplayTagCountUpdateResult::Updated { applied_delta: 1 });
+    }
+
+    #[test]
+    fn unregistered_inhibitor_hides_and_reveals_tag() {
+        let (mut world, entity) = setup_world();
+        world
+            .resource_mut::<GameplayTagsManager>()
+            .add_tag_inhibition(GameplayTag::new("X.Suppressed"), GameplayTag::new("A.B"));
+        update_tag_count(&mut world, entity, "A.B.C", 1);
+        update_tag_count(&mut world, entity, "X.Suppressed.Strong", 1);
+
+        let tag = GameplayTag::new("A.B.C");
+        let count_container = world.get::<GameplayTagCountContainer>(entity).unwrap();
+        assert!(count_container.is_tag_inhibited(&tag));
+        assert!(!count_container.has_matching_gameplay_tag(&GameplayTag::new("A.B")));
+        assert_eq!(count_container.get_explicit_tag_count(&tag), 1);
+
+        update_tag_count(&mut world, entity, "X.Suppressed.Strong", -1);
+        let count_container = world.get::<GameplayTagCountContainer>(entity).unwrap();
+        assert!(!count_container.is_tag_inhibited(&tag));
+        assert!(count_container.has_matching_gameplay_tag(&GameplayTag::new("A.B")));
+    }
+
+    #[test]
+    fn one_update_triggers_one_internal_notification() {
+        let (mut world, entity) = setup_world();
+        world.init_resource::<NotificationSizes>();
+        world.add_observer(
+            |trigger: On<GameplayTagCountNotification>, mut world: DeferredWorld| {
+                let num_changes = trigger.event().changes.len();
+                world.resource_mut::<NotificationSizes>().0.push(num_changes);
+            },
+        );
+        update_tag_count(&mut world, entity, "A.B.C", 1);
+        world.modify_gameplay_tags(entity, |tx| {
+            tx.add_tag(&GameplayTag::new("A.B.D"), 1);
+            tx.remove_tag(&GameplayTag::new("A.B.C"), 1);
+        });
+
+        //A.B.C及两个父标签各有NewOrRemoved和AnyCountChanged；事务中A.B和A的计数没有净变化
+        assert_eq!(world.resource::<NotificationSizes>().0, [6, 4]);
+    }
+
+    fn record_changes(world: &mut World) {
+        world.init_resource::<SeenChanges>();
+        world.add_observer(
+            |trigger: On<OnGameplayEffectTagCountChanged>, mut world: DeferredWorld| {
+                let change = trigger.event().clone();
+                world.resource_mut::<SeenChanges>().0.push(change);
+            },
+        );
+    }
+
+    #[test]
+    fn parent_events_carry_cause_and_source() {
+        let (mut world, entity) = setup_world();
+        record_changes(&mut world);
+        let source = world.spawn_empty().id();
+        update_tag_count(&mut world, entity, "A.B.C", 2);
+        world.modify_gameplay_tags(entity, |tx| {
+            tx.update_tag_count_from_source(&GameplayTag::new("A.B.D"), 1, source)
+        });
+
+        let seen = &world.resource::<SeenChanges>().0;
+        let parent_change = seen
+            .iter()
+            .rfind(|change| {
+                change.tag == GameplayTag::new("A.B")
+                    && change.event_type == GameplayTagEventType::AnyCountChanged
+            })
+            .unwrap();
+        assert_eq!(
+            (
+                parent_change.old_count,
+                parent_change.new_count,
+                parent_change.delta
+            ),
+            (2, 3, 1)
+        );
+        assert!(!parent_change.is_explicit);
+        assert_eq!(parent_change.cause_tag, GameplayTag::new("A.B.D"));
+        assert_eq!(parent_change.source, Some(source));
+        let explicit_change = seen
+            .iter()
+            .find(|change| change.tag == GameplayTag::new("A.B.C"))
+            .unwrap();
+        assert!(explicit_change.is_explicit);
+        assert_eq!(explicit_change.source, None);
+    }
+
+    #[test]
+    fn suppressed_parent_events_still_reach_internal_notification() {
+        let (mut world, entity) = setup_world();
+        record_changes(&mut world);
+        world.init_resource::<NotificationSizes>();
+        world.add_observer(
+            |trigger: On<GameplayTagCountNotification>, mut world: DeferredWorld| {
+                let num_changes = trigger.event().changes.len();
+                world
+                    .resource_mut::<NotificationSizes>()
+                    .0
+                    .push(num_changes);
+            },
+        );
+        world
+            .get_mut::<GameplayTagCountContainer>(entity)
+            .unwrap()
+            .set_suppress_implicit_parent_events(true);
+        update_tag_count(&mut world, entity, "A.B.C", 1);
+
+        let seen = &world.resource::<SeenChanges>().0;
+        assert_eq!(seen.len(), 2);
+        assert!(
+            seen.iter()
+                .all(|change| change.is_explicit && change.tag == GameplayTag::new("A.B.C"))
+        );
+        assert_eq!(world.resource::<NotificationSizes>().0, [6]);
+    }
 }
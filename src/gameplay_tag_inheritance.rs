@@ -0,0 +1,381 @@
+use bevy::ecs::{
+    component::Component,
+    entity::{Entity, EntityHashSet},
+    event::EntityEvent,
+    hierarchy::{ChildOf, Children},
+    lifecycle::{Insert, Remove},
+    observer::On,
+    query::{Changed, Or},
+    resource::Resource,
+    system::{Commands, Query, Res, ResMut},
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_container::GameplayTagContainer,
+    gameplay_tag_count_container::{GameplayTagCountContainer, GameplayTagCountNotification},
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+///
+/// Opt-in view of an entity's own tags combined with the tags of its `ChildOf` ancestors.
+///
+/// A weapon that is a child of the player can check `has_tag(Teams.Player)` without copying the
+/// tag. Only ancestor tags passing the filter are inherited: a tag is inherited if it matches one of
+/// the include tags (or the include list is empty) and none of the exclude tags. The plugin keeps
+/// the view up to date when the tags of the entity or of an ancestor change, and when the entity
+/// is re-parented.
+///
+#[derive(Component, Debug, Default)]
+pub struct InheritedGameplayTags {
+    include_tags: GameplayTagContainer,
+    exclude_tags: GameplayTagContainer,
+    inherited_tags: GameplayTagContainer,
+    combined_tags: GameplayTagContainer,
+}
+
+impl InheritedGameplayTags {
+    /// Inherits every tag of the ancestors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inherits only the ancestor tags matching `include_tags` and none of `exclude_tags`.
+    pub fn with_filter(
+        include_tags: GameplayTagContainer,
+        exclude_tags: GameplayTagContainer,
+    ) -> Self {
+        InheritedGameplayTags {
+            include_tags,
+            exclude_tags,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Returns `true` if an ancestor's `tag` passes this view's filter.
+    /// Tags missing from the tag table are matched against the filter by name.
+    ///
+    pub fn is_inheritable(&self, tag: &GameplayTag, tags_manager: &GameplayTagsManager) -> bool {
+        let complete_container = tags_manager.get_single_tag_container(tag);
+        let matches_any = |filter_tags: &GameplayTagContainer| match complete_container {
+            Some(complete_container) => complete_container.has_any(filter_tags),
+            None => filter_tags
+                .get_gameplay_tags()
+                .iter()
+                .any(|filter_tag| tag.matches_tag_name(filter_tag)),
+        };
+        (self.include_tags.is_empty() || matches_any(&self.include_tags))
+            && !matches_any(&self.exclude_tags)
+    }
+
+    /// Returns the tags inherited from the ancestors, after filtering.
+    pub fn get_inherited_tags(&self) -> &GameplayTagContainer {
+        &self.inherited_tags
+    }
+
+    /// Returns the entity's own explicit tags together with the inherited tags.
+    pub fn get_combined_tags(&self) -> &GameplayTagContainer {
+        &self.combined_tags
+    }
+
+    /// Returns `true` if the entity has `tag` itself or inherits it.
+    pub fn has_tag(&self, tag: &GameplayTag) -> bool {
+        self.combined_tags.has_tag(tag)
+    }
+}
+
+///
+/// Triggered on an entity when the combined tags of its `InheritedGameplayTags` changed.
+/// `added` and `removed` only list explicit tags.
+///
+#[derive(EntityEvent, Debug)]
+pub struct InheritedGameplayTagsChanged {
+    pub entity: Entity,
+    pub added: Vec<GameplayTag>,
+    pub removed: Vec<GameplayTag>,
+}
+
+//标签或父子关系发生变化的实体，其自身及所有后代的继承标签需要重新计算
+#[derive(Resource, Debug, Default)]
+pub(crate) struct DirtyInheritedGameplayTags {
+    entities: EntityHashSet,
+}
+
+pub(crate) type GameplayTagSources<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Option<&'static GameplayTagCountContainer>,
+        Option<&'static GameplayTagContainer>,
+    ),
+>;
+
+/// Collects the explicit tags of `entity` from its `GameplayTagCountContainer` and `GameplayTagContainer`.
+pub(crate) fn collect_entity_explicit_tags(
+    entity: Entity,
+    tag_sources: &GameplayTagSources,
+    explicit_tags: &mut Vec<GameplayTag>,
+) {
+    if let Ok((count_container, container)) = tag_sources.get(entity) {
+        if let Some(count_container) = count_container {
+            explicit_tags.extend(
+                count_container
+                    .get_explicit_tags()
+                    .get_gameplay_tags()
+                    .iter()
+                    .cloned(),
+            );
+        }
+        if let Some(container) = container {
+            explicit_tags.extend(container.get_gameplay_tags().iter().cloned());
+        }
+    }
+}
+
+///
+/// Builds a normalized container from `tags`. Tags are not added one by one with `add_tag`, so
+/// exclusive groups do not remove each other's tags from the view.
+///
+pub(crate) fn build_tag_view(
+    tags: Vec<GameplayTag>,
+    tags_manager: &GameplayTagsManager,
+) -> GameplayTagContainer {
+    let mut container = GameplayTagContainer::new();
+    container.gameplay_tags = tags;
+    container.normalize(tags_manager);
+    container
+}
+
+/// Returns the explicit tags of `new_tags` missing from `old_tags`.
+pub(crate) fn diff_explicit_tags(
+    old_tags: &GameplayTagContainer,
+    new_tags: &GameplayTagContainer,
+) -> Vec<GameplayTag> {
+    new_tags
+        .get_gameplay_tags()
+        .iter()
+        .filter(|tag| !old_tags.has_tag_exact(tag))
+        .cloned()
+        .collect()
+}
+
+pub(crate) fn mark_inherited_tags_dirty_on_count_changed(
+    trigger: On<GameplayTagCountNotification>,
+    mut dirty: ResMut<DirtyInheritedGameplayTags>,
+) {
+    let notification = trigger.event();
+    if notification.has_presence_changes() {
+        dirty.entities.insert(notification.entity);
+    }
+}
+
+pub(crate) fn mark_inherited_tags_dirty_on_parent_removed(
+    trigger: On<Remove, ChildOf>,
+    mut dirty: ResMut<DirtyInheritedGameplayTags>,
+) {
+    dirty.entities.insert(trigger.event().entity);
+}
+
+pub(crate) fn mark_inherited_tags_dirty_on_container_removed(
+    trigger: On<Remove, (GameplayTagContainer, GameplayTagCountContainer)>,
+    mut dirty: ResMut<DirtyInheritedGameplayTags>,
+) {
+    dirty.entities.insert(trigger.event().entity);
+}
+
+//插入视图（包括用新的过滤条件重新插入）时需要完整计算一次
+pub(crate) fn mark_inherited_tags_dirty_on_view_inserted(
+    trigger: On<Insert, InheritedGameplayTags>,
+    mut dirty: ResMut<DirtyInheritedGameplayTags>,
+) {
+    dirty.entities.insert(trigger.event().entity);
+}
+
+type ChangedInheritanceSources<'w, 's> =
+    Query<'w, 's, Entity, Or<(Changed<GameplayTagContainer>, Changed<ChildOf>)>>;
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_inherited_gameplay_tags(
+    changed: ChangedInheritanceSources,
+    mut dirty: ResMut<DirtyInheritedGameplayTags>,
+    children: Query<&Children>,
+    parents: Query<&ChildOf>,
+    tag_sources: GameplayTagSources,
+    mut views: Query<&mut InheritedGameplayTags>,
+    tags_manager: Res<GameplayTagsManager>,
+    mut commands: Commands,
+) {
+    let mut dirty_entities = std::mem::take(&mut dirty.entities);
+    if views.is_empty() {
+        return;
+    }
+    dirty_entities.extend(changed.iter());
+    if dirty_entities.is_empty() {
+        return;
+    }
+
+    //从变化的实体向下遍历，只重新计算带有继承视图的实体
+    let mut visited = EntityHashSet::default();
+    let mut pending: Vec<Entity> = dirty_entities.into_iter().collect();
+    while let Some(entity) = pending.pop() {
+        if !visited.insert(entity) {
+            continue;
+        }
+        if let Ok(children) = children.get(entity) {
+            pending.extend(children.iter());
+        }
+        let Ok(view) = views.get(entity) else {
+            continue;
+        };
+
+        let mut inherited = Vec::new();
+        let mut current = entity;
+        while let Ok(child_of) = parents.get(current) {
+            current = child_of.parent();
+            collect_entity_explicit_tags(current, &tag_sources, &mut inherited);
+        }
+        inherited.retain(|tag| view.is_inheritable(tag, &tags_manager));
+        let mut combined = inherited.clone();
+        collect_entity_explicit_tags(entity, &tag_sources, &mut combined);
+
+        let inherited_tags = build_tag_view(inherited, &tags_manager);
+        let combined_tags = build_tag_view(combined, &tags_manager);
+        let added = diff_explicit_tags(&view.combined_tags, &combined_tags);
+        let removed = diff_explicit_tags(&combined_tags, &view.combined_tags);
+        if added.is_empty()
+            && removed.is_empty()
+            && view.inherited_tags.get_gameplay_tags() == inherited_tags.get_gameplay_tags()
+        {
+            continue;
+        }
+
+        if let Ok(mut view) = views.get_mut(entity) {
+            view.inherited_tags = inherited_tags;
+            view.combined_tags = combined_tags;
+        }
+        if !added.is_empty() || !removed.is_empty() {
+            commands.trigger(InheritedGameplayTagsChanged {
+                entity,
+                added,
+                removed,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{
+        schedule::Schedule,
+        world::{DeferredWorld, World},
+    };
+
+    use super::*;
+    use crate::gameplay_tag_commands::GameplayTagWorldExt;
+
+    #[derive(Resource, Default)]
+    struct ViewChanges(Vec<(Vec<GameplayTag>, Vec<GameplayTag>)>);
+
+    fn setup_world() -> (World, Schedule) {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<DirtyInheritedGameplayTags>();
+        world.init_resource::<ViewChanges>();
+        world.add_observer(mark_inherited_tags_dirty_on_count_changed);
+        world.add_observer(mark_inherited_tags_dirty_on_parent_removed);
+        world.add_observer(mark_inherited_tags_dirty_on_container_removed);
+        world.add_observer(mark_inherited_tags_dirty_on_view_inserted);
+        world.add_observer(
+            |trigger: On<InheritedGameplayTagsChanged>, mut world: DeferredWorld| {
+                let event = trigger.event();
+                world
+                    .resource_mut::<ViewChanges>()
+                    .0
+                    .push((event.added.clone(), event.removed.clone()));
+            },
+        );
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_inherited_gameplay_tags);
+        (world, schedule)
+    }
+
+    fn spawn_with_tag(world: &mut World, tag_name: &str) -> Entity {
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        world.modify_gameplay_tags(entity, |tx| {
+            tx.update_tag_count(&GameplayTag::new(tag_name), 1)
+        });
+        entity
+    }
+
+    fn has_tag(world: &World, entity: Entity, tag_name: &str) -> bool {
+        world
+            .get::<InheritedGameplayTags>(entity)
+            .unwrap()
+            .has_tag(&GameplayTag::new(tag_name))
+    }
+
+    #[test]
+    fn view_follows_ancestor_tags_across_re_parenting() {
+        let (mut world, mut schedule) = setup_world();
+        let first_owner = spawn_with_tag(&mut world, "A.B.C");
+        let second_owner = spawn_with_tag(&mut world, "D.C");
+        let weapon = spawn_with_tag(&mut world, "A.C");
+        world
+            .entity_mut(weapon)
+            .insert((InheritedGameplayTags::new(), ChildOf(first_owner)));
+        schedule.run(&mut world);
+        assert!(has_tag(&world, weapon, "A.B.C"));
+        assert!(has_tag(&world, weapon, "A.C"));
+
+        world.entity_mut(weapon).insert(ChildOf(second_owner));
+        world.resource_mut::<ViewChanges>().0.clear();
+        schedule.run(&mut world);
+        assert!(!has_tag(&world, weapon, "A.B.C"));
+        assert!(has_tag(&world, weapon, "D.C"));
+        assert_eq!(
+            world.resource::<ViewChanges>().0,
+            vec![(
+                vec![GameplayTag::new("D.C")],
+                vec![GameplayTag::new("A.B.C")]
+            )]
+        );
+
+        world.modify_gameplay_tags(second_owner, |tx| {
+            tx.update_tag_count(&GameplayTag::new("Status.Damaged"), 1)
+        });
+        schedule.run(&mut world);
+        assert!(has_tag(&world, weapon, "Status.Damaged"));
+
+        world.entity_mut(weapon).remove::<ChildOf>();
+        schedule.run(&mut world);
+        let view = world.get::<InheritedGameplayTags>(weapon).unwrap();
+        assert!(view.get_inherited_tags().is_empty());
+        assert!(view.has_tag(&GameplayTag::new("A.C")));
+    }
+
+    #[test]
+    fn filter_limits_inherited_tags() {
+        let (mut world, mut schedule) = setup_world();
+        let owner = spawn_with_tag(&mut world, "A.B.C");
+        world.modify_gameplay_tags(owner, |tx| {
+            tx.update_tag_count(&GameplayTag::new("A.B.D"), 1);
+            tx.update_tag_count(&GameplayTag::new("D.C"), 1)
+        });
+        let mut include_tags = GameplayTagContainer::new();
+        include_tags.gameplay_tags.push(GameplayTag::new("A"));
+        let mut exclude_tags = GameplayTagContainer::new();
+        exclude_tags.gameplay_tags.push(GameplayTag::new("A.B.D"));
+        let child = world
+            .spawn((
+                InheritedGameplayTags::with_filter(include_tags, exclude_tags),
+                ChildOf(owner),
+            ))
+            .id();
+        schedule.run(&mut world);
+
+        assert!(has_tag(&world, child, "A.B.C"));
+        assert!(!has_tag(&world, child, "A.B.D"));
+        assert!(!has_tag(&world, child, "D.C"));
+    }
+}
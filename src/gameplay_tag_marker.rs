@@ -0,0 +1,198 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::{
+    component::Component,
+    entity::Entity,
+    lifecycle::Remove,
+    observer::On,
+    query::{Changed, Has},
+    resource::Resource,
+    system::{Commands, Query, Res},
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_container::GameplayTagContainer,
+    gameplay_tag_count_container::{GameplayTagCountContainer, GameplayTagCountNotification},
+};
+
+///
+/// Binds a gameplay tag to the zero-sized marker component `M`.
+///
+/// While the tag, or any of its descendants, is present on an entity's `GameplayTagCountContainer`
+/// or `GameplayTagContainer`, the plugin keeps `M` inserted on that entity, so systems can filter
+/// with `With<M>` / `Without<M>` instead of matching tags. Register it with
+/// `GameplayTagsAppExt::add_gameplay_tag_marker`.
+///
+#[derive(Resource, Debug)]
+pub struct GameplayTagMarkerBinding<M: Component + Default> {
+    tag: GameplayTag,
+    _marker: PhantomData<M>,
+}
+
+impl<M: Component + Default> GameplayTagMarkerBinding<M> {
+    pub fn new(tag: GameplayTag) -> Self {
+        Self {
+            tag,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn get_tag(&self) -> &GameplayTag {
+        &self.tag
+    }
+
+    fn is_present(
+        &self,
+        count_container: Option<&GameplayTagCountContainer>,
+        container: Option<&GameplayTagContainer>,
+    ) -> bool {
+        count_container.is_some_and(|counts| counts.has_matching_gameplay_tag(&self.tag))
+            || container.is_some_and(|tags| tags.has_tag(&self.tag))
+    }
+}
+
+fn sync_marker<M: Component + Default>(
+    commands: &mut Commands,
+    entity: Entity,
+    is_present: bool,
+    has_marker: bool,
+) {
+    if is_present && !has_marker {
+        commands.entity(entity).try_insert(M::default());
+    } else if !is_present && has_marker {
+        commands.entity(entity).try_remove::<M>();
+    }
+}
+
+pub(crate) fn sync_tag_marker_on_count_changed<M: Component + Default>(
+    trigger: On<GameplayTagCountNotification>,
+    binding: Res<GameplayTagMarkerBinding<M>>,
+    query: Query<(
+        Option<&GameplayTagCountContainer>,
+        Option<&GameplayTagContainer>,
+        Has<M>,
+    )>,
+    mut commands: Commands,
+) {
+    let notification = trigger.event();
+    //父标签也在变化列表中，所以只需要关注绑定的标签本身
+    if !notification
+        .presence_changes()
+        .any(|change| change.tag == binding.tag)
+    {
+        return;
+    }
+    if let Ok((count_container, container, has_marker)) = query.get(notification.entity) {
+        let is_present = binding.is_present(count_container, container);
+        sync_marker::<M>(&mut commands, notification.entity, is_present, has_marker);
+    }
+}
+
+type ChangedContainerMarkerData<'a, M> = (
+    Entity,
+    &'a GameplayTagContainer,
+    Option<&'a GameplayTagCountContainer>,
+    Has<M>,
+);
+
+pub(crate) fn sync_tag_marker_on_container_changed<M: Component + Default>(
+    binding: Res<GameplayTagMarkerBinding<M>>,
+    query: Query<ChangedContainerMarkerData<M>, Changed<GameplayTagContainer>>,
+    mut commands: Commands,
+) {
+    for (entity, container, count_container, has_marker) in query.iter() {
+        let is_present = binding.is_present(count_container, Some(container));
+        sync_marker::<M>(&mut commands, entity, is_present, has_marker);
+    }
+}
+
+pub(crate) fn sync_tag_marker_on_container_removed<M: Component + Default>(
+    trigger: On<Remove, GameplayTagContainer>,
+    binding: Res<GameplayTagMarkerBinding<M>>,
+    query: Query<(Option<&GameplayTagCountContainer>, Has<M>)>,
+    mut commands: Commands,
+) {
+    let entity = trigger.event().entity;
+    if let Ok((count_container, has_marker)) = query.get(entity) {
+        //移除观察者运行时组件还在，这里只看剩下的计数容器
+        let is_present = binding.is_present(count_container, None);
+        sync_marker::<M>(&mut commands, entity, is_present, has_marker);
+    }
+}
+
+pub(crate) fn sync_tag_marker_on_count_container_removed<M: Component + Default>(
+    trigger: On<Remove, GameplayTagCountContainer>,
+    binding: Res<GameplayTagMarkerBinding<M>>,
+    query: Query<(Option<&GameplayTagContainer>, Has<M>)>,
+    mut commands: Commands,
+) {
+    let entity = trigger.event().entity;
+    if let Ok((container, has_marker)) = query.get(entity) {
+        let is_present = binding.is_present(None, container);
+        sync_marker::<M>(&mut commands, entity, is_present, has_marker);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::World;
+
+    use super::*;
+    use crate::{
+        gameplay_tag_commands::GameplayTagWorldExt, gameplay_tags_manager::GameplayTagsManager,
+    };
+
+    #[derive(Component, Default)]
+    struct MarkedAB;
+
+    fn setup_world() -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.insert_resource(GameplayTagMarkerBinding::<MarkedAB>::new(GameplayTag::new(
+            "A.B",
+        )));
+        world.add_observer(sync_tag_marker_on_count_changed::<MarkedAB>);
+        world.add_observer(sync_tag_marker_on_count_container_removed::<MarkedAB>);
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        (world, entity)
+    }
+
+    fn update_tag_count(world: &mut World, entity: Entity, tag_name: &str, count_delta: i32) {
+        world
+            .modify_gameplay_tags(entity, |tx| {
+                tx.update_tag_count(&GameplayTag::new(tag_name), count_delta)
+            })
+            .unwrap();
+        world.flush();
+    }
+
+    #[test]
+    fn marker_follows_bound_tag_and_its_descendants() {
+        let (mut world, entity) = setup_world();
+        update_tag_count(&mut world, entity, "A.C", 1);
+        assert!(!world.entity(entity).contains::<MarkedAB>());
+
+        update_tag_count(&mut world, entity, "A.B.C", 1);
+        assert!(world.entity(entity).contains::<MarkedAB>());
+        update_tag_count(&mut world, entity, "A.B.D", 1);
+        update_tag_count(&mut world, entity, "A.B.C", -1);
+        assert!(world.entity(entity).contains::<MarkedAB>());
+
+        update_tag_count(&mut world, entity, "A.B.D", -1);
+        assert!(!world.entity(entity).contains::<MarkedAB>());
+    }
+
+    #[test]
+    fn removing_count_container_removes_marker() {
+        let (mut world, entity) = setup_world();
+        update_tag_count(&mut world, entity, "A.B.C", 1);
+        assert!(world.entity(entity).contains::<MarkedAB>());
+
+        world
+            .entity_mut(entity)
+            .remove::<GameplayTagCountContainer>();
+        world.flush();
+        assert!(!world.entity(entity).contains::<MarkedAB>());
+    }
+}
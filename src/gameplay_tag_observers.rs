@@ -0,0 +1,433 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        entity_disabling::Internal,
+        event::EntityEvent,
+        lifecycle::{Insert, Remove},
+        observer::{Observer, On},
+        query::Allow,
+        resource::Resource,
+        system::{Commands, EntityCommands, IntoObserverSystem, Query, Res, ResMut},
+    },
+    platform::collections::HashMap,
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_count_container::{
+        GameplayTagCountNotification, GameplayTagEventType, OnGameplayEffectTagCountChanged,
+    },
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+///
+/// Triggered on a tag-scoped observer registered with `observe_tag` or `observe_tag_subtree`, for
+/// each `OnGameplayEffectTagCountChanged` that passes its filter. The fields mirror the original
+/// event: `entity` is the entity whose tag count changed.
+///
+#[derive(EntityEvent, Debug)]
+pub struct OnScopedGameplayTagChanged {
+    #[event_target]
+    observer: Entity,
+    pub entity: Entity,
+    pub tag: GameplayTag,
+    pub new_count: i32,
+    pub event_type: GameplayTagEventType,
+    pub old_count: i32,
+    pub delta: i32,
+    pub is_explicit: bool,
+    pub cause_tag: GameplayTag,
+    pub source: Option<Entity>,
+}
+
+///
+/// Filter of a tag-scoped observer, stored on the observer entity next to its `Observer`.
+///
+/// The observer is invoked for events of `event_type` on `tag`, and also on its descendants if
+/// `include_descendants` is set. `watched_entity` restricts it to the tag changes of one entity.
+///
+#[derive(Component, Debug, Clone)]
+pub struct GameplayTagObserverFilter {
+    pub tag: GameplayTag,
+    pub event_type: GameplayTagEventType,
+    pub include_descendants: bool,
+    pub watched_entity: Option<Entity>,
+}
+
+impl GameplayTagObserverFilter {
+    fn accepts(&self, event: &OnGameplayEffectTagCountChanged) -> bool {
+        self.event_type == event.event_type
+            && (self.include_descendants || self.tag == event.tag)
+            && self
+                .watched_entity
+                .is_none_or(|watched_entity| watched_entity == event.entity)
+    }
+}
+
+///
+/// Ties a tag-scoped observer entity to the entity it watches, so despawning the watched entity
+/// also despawns the observer. Inserted by `spawn_gameplay_tag_observer` for filters with a
+/// `watched_entity`.
+///
+#[derive(Component, Debug)]
+#[relationship(relationship_target = GameplayTagObservers)]
+pub struct GameplayTagObserverOf(pub Entity);
+
+/// The tag-scoped observers watching this entity.
+#[derive(Component, Debug)]
+#[relationship_target(relationship = GameplayTagObserverOf, linked_spawn)]
+pub struct GameplayTagObservers(Vec<Entity>);
+
+///
+/// Handle of a tag-scoped observer. The observer lives on its own entity, so unregistering it
+/// despawns that entity and leaves every other observer untouched.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GameplayTagObserverHandle {
+    observer: Entity,
+}
+
+impl GameplayTagObserverHandle {
+    /// Returns the entity holding the `Observer` and its `GameplayTagObserverFilter`.
+    pub fn entity(&self) -> Entity {
+        self.observer
+    }
+
+    pub fn unregister(self, commands: &mut Commands) {
+        commands.entity(self.observer).try_despawn();
+    }
+}
+
+//按过滤标签索引的观察者实体，派发时只需查找事件标签及其父标签
+#[derive(Resource, Debug, Default)]
+pub(crate) struct GameplayTagObserverRegistry {
+    observers: HashMap<GameplayTag, Vec<Entity>>,
+}
+
+///
+/// Spawns a tag-scoped observer entity running `handler` for events accepted by `filter`.
+/// If the filter has a `watched_entity`, the observer is despawned together with that entity.
+///
+pub fn spawn_gameplay_tag_observer<M>(
+    commands: &mut Commands,
+    filter: GameplayTagObserverFilter,
+    handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+) -> GameplayTagObserverHandle {
+    let observer = commands.spawn_empty().id();
+    let watched_entity = filter.watched_entity;
+    let mut observer_commands = commands.entity(observer);
+    observer_commands.insert((Observer::new(handler).with_entity(observer), filter));
+    if let Some(watched_entity) = watched_entity {
+        observer_commands.insert(GameplayTagObserverOf(watched_entity));
+    }
+    GameplayTagObserverHandle { observer }
+}
+
+/// Tag-scoped observers that are not tied to a single entity.
+pub trait GameplayTagObserverCommandsExt {
+    ///
+    /// Runs `handler` for `event_type` changes of exactly `tag` on any entity.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let handle = commands.observe_tag(
+    ///     GameplayTag::new("Status.Stunned"),
+    ///     GameplayTagEventType::NewOrRemoved,
+    ///     |trigger: On<OnScopedGameplayTagChanged>| { ... },
+    /// );
+    /// handle.unregister(&mut commands);
+    /// ```
+    ///
+    fn observe_tag<M>(
+        &mut self,
+        tag: GameplayTag,
+        event_type: GameplayTagEventType,
+        handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+    ) -> GameplayTagObserverHandle;
+
+    /// Runs `handler` for `event_type` changes of `tag` or any of its descendants on any entity.
+    fn observe_tag_subtree<M>(
+        &mut self,
+        tag: GameplayTag,
+        event_type: GameplayTagEventType,
+        handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+    ) -> GameplayTagObserverHandle;
+}
+
+impl GameplayTagObserverCommandsExt for Commands<'_, '_> {
+    fn observe_tag<M>(
+        &mut self,
+        tag: GameplayTag,
+        event_type: GameplayTagEventType,
+        handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+    ) -> GameplayTagObserverHandle {
+        let filter = GameplayTagObserverFilter {
+            tag,
+            event_type,
+            include_descendants: false,
+            watched_entity: None,
+        };
+        spawn_gameplay_tag_observer(self, filter, handler)
+    }
+
+    fn observe_tag_subtree<M>(
+        &mut self,
+        tag: GameplayTag,
+        event_type: GameplayTagEventType,
+        handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+    ) -> GameplayTagObserverHandle {
+        let filter = GameplayTagObserverFilter {
+            tag,
+            event_type,
+            include_descendants: true,
+            watched_entity: None,
+        };
+        spawn_gameplay_tag_observer(self, filter, handler)
+    }
+}
+
+///
+/// Tag-scoped observers that only watch the tag changes of this entity. They are despawned
+/// together with the entity.
+///
+pub trait GameplayTagObserverEntityCommandsExt {
+    /// Runs `handler` for `event_type` changes of exactly `tag` on this entity.
+    fn observe_tag<M>(
+        &mut self,
+        tag: GameplayTag,
+        event_type: GameplayTagEventType,
+        handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+    ) -> GameplayTagObserverHandle;
+
+    /// Runs `handler` for `event_type` changes of `tag` or any of its descendants on this entity.
+    fn observe_tag_subtree<M>(
+        &mut self,
+        tag: GameplayTag,
+        event_type: GameplayTagEventType,
+        handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+    ) -> GameplayTagObserverHandle;
+}
+
+impl GameplayTagObserverEntityCommandsExt for EntityCommands<'_> {
+    fn observe_tag<M>(
+        &mut self,
+        tag: GameplayTag,
+        event_type: GameplayTagEventType,
+        handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+    ) -> GameplayTagObserverHandle {
+        let filter = GameplayTagObserverFilter {
+            tag,
+            event_type,
+            include_descendants: false,
+            watched_entity: Some(self.id()),
+        };
+        spawn_gameplay_tag_observer(&mut self.commands(), filter, handler)
+    }
+
+    fn observe_tag_subtree<M>(
+        &mut self,
+        tag: GameplayTag,
+        event_type: GameplayTagEventType,
+        handler: impl IntoObserverSystem<OnScopedGameplayTagChanged, (), M>,
+    ) -> GameplayTagObserverHandle {
+        let filter = GameplayTagObserverFilter {
+            tag,
+            event_type,
+            include_descendants: true,
+            watched_entity: Some(self.id()),
+        };
+        spawn_gameplay_tag_observer(&mut self.commands(), filter, handler)
+    }
+}
+
+//观察者实体带有Internal组件，默认会被查询过滤掉
+type ObserverFilters<'w, 's> = Query<'w, 's, &'static GameplayTagObserverFilter, Allow<Internal>>;
+
+pub(crate) fn register_gameplay_tag_observer(
+    trigger: On<Insert, GameplayTagObserverFilter>,
+    filters: ObserverFilters,
+    mut registry: ResMut<GameplayTagObserverRegistry>,
+) {
+    let observer = trigger.event().entity;
+    if let Ok(filter) = filters.get(observer) {
+        registry
+            .observers
+            .entry(filter.tag.clone())
+            .or_default()
+            .push(observer);
+    }
+}
+
+//替换或移除过滤条件、销毁观察者实体时都会触发
+pub(crate) fn unregister_gameplay_tag_observer(
+    trigger: On<Remove, GameplayTagObserverFilter>,
+    filters: ObserverFilters,
+    mut registry: ResMut<GameplayTagObserverRegistry>,
+) {
+    let observer = trigger.event().entity;
+    let Ok(filter) = filters.get(observer) else {
+        return;
+    };
+    if let Some(observers) = registry.observers.get_mut(&filter.tag) {
+        observers.retain(|registered| *registered != observer);
+        if observers.is_empty() {
+            registry.observers.remove(&filter.tag);
+        }
+    }
+}
+
+pub(crate) fn dispatch_gameplay_tag_observers(
+    trigger: On<GameplayTagCountNotification>,
+    registry: Res<GameplayTagObserverRegistry>,
+    filters: ObserverFilters,
+    tags_manager: Res<GameplayTagsManager>,
+    mut commands: Commands,
+) {
+    if registry.observers.is_empty() {
+        return;
+    }
+    for event in trigger.event().changes.iter() {
+        //事件标签本身及其父标签上注册的观察者都可能匹配
+        let tag_and_parents = tags_manager.request_gameplay_tag_parents(&event.tag);
+        for filter_tag in tag_and_parents.get_gameplay_tags() {
+            let Some(observers) = registry.observers.get(filter_tag) else {
+                continue;
+            };
+            for observer in observers.iter() {
+                let Ok(filter) = filters.get(*observer) else {
+                    continue;
+                };
+                if filter.accepts(event) {
+                    commands.trigger(OnScopedGameplayTagChanged {
+                        observer: *observer,
+                        entity: event.entity,
+                        tag: event.tag.clone(),
+                        new_count: event.new_count,
+                        event_type: event.event_type,
+                        old_count: event.old_count,
+                        delta: event.delta,
+                        is_explicit: event.is_explicit,
+                        cause_tag: event.cause_tag.clone(),
+                        source: event.source,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::World;
+
+    use super::*;
+    use crate::{
+        gameplay_tag_commands::GameplayTagWorldExt,
+        gameplay_tag_count_container::GameplayTagCountContainer,
+    };
+
+    #[derive(Resource, Default)]
+    struct SeenEvents(Vec<(&'static str, GameplayTag)>);
+
+    fn setup_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<GameplayTagObserverRegistry>();
+        world.init_resource::<SeenEvents>();
+        world.add_observer(register_gameplay_tag_observer);
+        world.add_observer(unregister_gameplay_tag_observer);
+        world.add_observer(dispatch_gameplay_tag_observers);
+        world
+    }
+
+    fn recorder(
+        label: &'static str,
+    ) -> impl FnMut(On<OnScopedGameplayTagChanged>, ResMut<SeenEvents>) {
+        move |trigger: On<OnScopedGameplayTagChanged>, mut seen: ResMut<SeenEvents>| {
+            seen.0.push((label, trigger.event().tag.clone()));
+        }
+    }
+
+    fn update_tag_count(world: &mut World, entity: Entity, tag_name: &str, count_delta: i32) {
+        world.modify_gameplay_tags(entity, |tx| {
+            tx.update_tag_count(&GameplayTag::new(tag_name), count_delta)
+        });
+        world.flush();
+    }
+
+    fn take_seen(world: &mut World, label: &str) -> Vec<GameplayTag> {
+        let seen = std::mem::take(&mut world.resource_mut::<SeenEvents>().0);
+        seen.into_iter()
+            .filter(|(seen_label, _)| *seen_label == label)
+            .map(|(_, tag)| tag)
+            .collect()
+    }
+
+    #[test]
+    fn unregistering_one_observer_keeps_the_others() {
+        let mut world = setup_world();
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        let exact = world.commands().entity(entity).observe_tag(
+            GameplayTag::new("A.B.C"),
+            GameplayTagEventType::NewOrRemoved,
+            recorder("exact"),
+        );
+        world.commands().observe_tag_subtree(
+            GameplayTag::new("A.B"),
+            GameplayTagEventType::NewOrRemoved,
+            recorder("subtree"),
+        );
+        world.flush();
+
+        update_tag_count(&mut world, entity, "A.B.C", 1);
+        assert_eq!(
+            take_seen(&mut world, "exact"),
+            vec![GameplayTag::new("A.B.C")]
+        );
+        update_tag_count(&mut world, entity, "A.B.D", 1);
+        let mut subtree_tags = take_seen(&mut world, "subtree");
+        subtree_tags.sort();
+        assert_eq!(subtree_tags, vec![GameplayTag::new("A.B.D")]);
+
+        exact.unregister(&mut world.commands());
+        world.flush();
+        update_tag_count(&mut world, entity, "A.B.C", -1);
+        assert!(
+            world
+                .resource::<SeenEvents>()
+                .0
+                .iter()
+                .all(|(label, _)| *label == "subtree")
+        );
+        assert_eq!(
+            take_seen(&mut world, "subtree"),
+            vec![GameplayTag::new("A.B.C")]
+        );
+    }
+
+    #[test]
+    fn entity_observer_is_despawned_with_watched_entity() {
+        let mut world = setup_world();
+        let watched = world.spawn(GameplayTagCountContainer::new()).id();
+        let other = world.spawn(GameplayTagCountContainer::new()).id();
+        let handle = world.commands().entity(watched).observe_tag(
+            GameplayTag::new("A.B.C"),
+            GameplayTagEventType::NewOrRemoved,
+            recorder("watched"),
+        );
+        world.flush();
+
+        update_tag_count(&mut world, other, "A.B.C", 1);
+        assert!(take_seen(&mut world, "watched").is_empty());
+
+        world.despawn(watched);
+        assert!(world.get_entity(handle.entity()).is_err());
+        assert!(
+            world
+                .resource::<GameplayTagObserverRegistry>()
+                .observers
+                .is_empty()
+        );
+    }
+}
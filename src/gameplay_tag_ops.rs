@@ -0,0 +1,245 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        resource::Resource,
+        system::{Res, SystemParam},
+        world::World,
+    },
+    utils::Parallel,
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_commands::{GameplayTagCountOp, GameplayTagWorldExt},
+    gameplay_tag_count_container::GameplayTagCountContainer,
+};
+
+#[derive(Debug, Clone)]
+struct QueuedGameplayTagOp {
+    entity: Entity,
+    sort_key: u64,
+    sequence: u64,
+    tag: GameplayTag,
+    op: GameplayTagCountOp,
+}
+
+/// Thread-local buffers behind `GameplayTagOps`, drained once per frame by the plugin.
+#[derive(Resource, Default)]
+pub struct GameplayTagOpQueue {
+    ops: Parallel<Vec<QueuedGameplayTagOp>>,
+    //所有线程共享的记录序号，同一个排序键内按记录的先后顺序执行
+    next_sequence: AtomicU64,
+}
+
+///
+/// Records tag operations without `Commands` or mutable access to any container, so it can be
+/// used from `par_iter` / `par_iter_mut`. Each thread writes to its own buffer.
+///
+/// The plugin applies the recorded operations in `PostUpdate`, before the conversion rules and the
+/// inherited and related tag views are updated. Operations recorded after that, e.g. by systems
+/// later in `PostUpdate` or in `Last`, are applied in the next frame's `PostUpdate`.
+///
+/// Entities are processed sorted by `Entity`, and each entity's operations run one by one as one
+/// transaction, see `GameplayTagWorldExt::modify_gameplay_tags`. Stacking limits therefore see
+/// every intermediate count, exactly as if the operations had been applied directly. Adding tags to
+/// an entity without a `GameplayTagCountContainer` inserts one; operations on despawned entities
+/// are dropped.
+///
+/// # Order
+/// An entity's operations run in ascending sort key order, see `with_sort_key`; operations
+/// recorded without one have the key 0. Operations with the same key run in the order they were
+/// recorded. That order is only deterministic for operations recorded one after another, e.g. by
+/// one system, or by one `par_iter` item. When several `par_iter` items record operations for the
+/// same entity, the threads race, so give each item its own sort key, e.g. its index or its
+/// `Entity` bits.
+///
+/// # Examples
+/// ```ignore
+/// fn burn_units(units: Query<(Entity, &Temperature)>, ops: GameplayTagOps) {
+///     units.par_iter().for_each(|(entity, temperature)| {
+///         if temperature.0 > 100.0 {
+///             ops.add_tag(entity, &GameplayTag::new("Status.Burning"), 1);
+///         }
+///     });
+/// }
+///
+/// fn apply_auras(auras: Query<(Entity, &Aura)>, ops: GameplayTagOps) {
+///     auras.par_iter().for_each(|(aura_entity, aura)| {
+///         //多个光环会修改同一个目标，用光环实体作为排序键
+///         let ops = ops.with_sort_key(aura_entity.to_bits());
+///         ops.add_tag(aura.target, &aura.tag, 1);
+///     });
+/// }
+/// ```
+///
+#[derive(SystemParam)]
+pub struct GameplayTagOps<'w> {
+    queue: Res<'w, GameplayTagOpQueue>,
+}
+
+impl GameplayTagOps<'_> {
+    ///
+    /// Returns a recorder whose operations run after the operations with lower sort keys on the
+    /// same entity, no matter on which thread or in which order they were recorded.
+    ///
+    pub fn with_sort_key(&self, sort_key: u64) -> SortedGameplayTagOps<'_> {
+        SortedGameplayTagOps {
+            queue: &self.queue,
+            sort_key,
+        }
+    }
+
+    pub fn add_tag(&self, entity: Entity, tag: &GameplayTag, count: i32) {
+        self.with_sort_key(0).add_tag(entity, tag, count);
+    }
+
+    pub fn remove_tag(&self, entity: Entity, tag: &GameplayTag, count: i32) {
+        self.with_sort_key(0).remove_tag(entity, tag, count);
+    }
+
+    pub fn update_tag_count(&self, entity: Entity, tag: &GameplayTag, count_delta: i32) {
+        self.with_sort_key(0)
+            .update_tag_count(entity, tag, count_delta);
+    }
+
+    pub fn set_tag_count(&self, entity: Entity, tag: &GameplayTag, new_count: i32) {
+        self.with_sort_key(0).set_tag_count(entity, tag, new_count);
+    }
+}
+
+/// Records tag operations with a fixed sort key, see `GameplayTagOps::with_sort_key`.
+pub struct SortedGameplayTagOps<'a> {
+    queue: &'a GameplayTagOpQueue,
+    sort_key: u64,
+}
+
+impl SortedGameplayTagOps<'_> {
+    pub fn add_tag(&self, entity: Entity, tag: &GameplayTag, count: i32) {
+        self.push(entity, tag, GameplayTagCountOp::Update(count));
+    }
+
+    pub fn remove_tag(&self, entity: Entity, tag: &GameplayTag, count: i32) {
+        self.push(entity, tag, GameplayTagCountOp::Update(-count));
+    }
+
+    pub fn update_tag_count(&self, entity: Entity, tag: &GameplayTag, count_delta: i32) {
+        self.push(entity, tag, GameplayTagCountOp::Update(count_delta));
+    }
+
+    pub fn set_tag_count(&self, entity: Entity, tag: &GameplayTag, new_count: i32) {
+        self.push(entity, tag, GameplayTagCountOp::Set(new_count));
+    }
+
+    fn push(&self, entity: Entity, tag: &GameplayTag, op: GameplayTagCountOp) {
+        let sequence = self.queue.next_sequence.fetch_add(1, Ordering::Relaxed);
+        self.queue.ops.borrow_local_mut().push(QueuedGameplayTagOp {
+            entity,
+            sort_key: self.sort_key,
+            sequence,
+            tag: tag.clone(),
+            op,
+        });
+    }
+}
+
+pub(crate) fn apply_gameplay_tag_ops(world: &mut World) {
+    let Some(mut queue) = world.get_resource_mut::<GameplayTagOpQueue>() else {
+        return;
+    };
+    let mut queued_ops: Vec<QueuedGameplayTagOp> = Vec::new();
+    for thread_ops in queue.ops.iter_mut() {
+        queued_ops.append(thread_ops);
+    }
+    if queued_ops.is_empty() {
+        return;
+    }
+    //排序只依赖实体、排序键和记录序号，与操作写入哪个线程的缓冲区无关
+    queued_ops.sort_unstable_by_key(|queued_op| {
+        (queued_op.entity, queued_op.sort_key, queued_op.sequence)
+    });
+
+    let mut entity_ops: Vec<(GameplayTag, GameplayTagCountOp)> = Vec::new();
+    let mut queued_ops = queued_ops.into_iter().peekable();
+    while let Some(queued_op) = queued_ops.next() {
+        let entity = queued_op.entity;
+        entity_ops.push((queued_op.tag, queued_op.op));
+        if queued_ops
+            .peek()
+            .is_some_and(|next_op| next_op.entity == entity)
+        {
+            continue;
+        }
+        apply_entity_tag_ops(world, entity, std::mem::take(&mut entity_ops));
+    }
+}
+
+fn apply_entity_tag_ops(
+    world: &mut World,
+    entity: Entity,
+    entity_ops: Vec<(GameplayTag, GameplayTagCountOp)>,
+) {
+    let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+        return;
+    };
+    if !entity_mut.contains::<GameplayTagCountContainer>() {
+        if !entity_ops.iter().any(|(_, op)| op.adds_tags()) {
+            return;
+        }
+        entity_mut.insert(GameplayTagCountContainer::new());
+    }
+    world.modify_gameplay_tags(entity, |tx| {
+        for (tag, op) in entity_ops.iter() {
+            match op {
+                GameplayTagCountOp::Update(count_delta) => tx.update_tag_count(tag, *count_delta),
+                GameplayTagCountOp::Set(new_count) => tx.set_tag_count(tag, *new_count),
+            };
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::{component::Component, system::Query, system::RunSystemOnce},
+        tasks::{ComputeTaskPool, TaskPool},
+    };
+
+    use super::*;
+    use crate::gameplay_tags_manager::GameplayTagsManager;
+
+    #[derive(Component)]
+    struct Emitter {
+        sort_key: u64,
+        target: Entity,
+    }
+
+    #[test]
+    fn par_iter_ops_on_one_entity_apply_in_sort_key_order() {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<GameplayTagOpQueue>();
+        let target = world.spawn_empty().id();
+        //生成顺序和排序键顺序不同，遍历顺序不能决定结果
+        for sort_key in [3, 7, 0, 5, 1, 6, 2, 4] {
+            world.spawn(Emitter { sort_key, target });
+        }
+
+        world
+            .run_system_once(|emitters: Query<&Emitter>, ops: GameplayTagOps| {
+                emitters.par_iter().for_each(|emitter| {
+                    let ops = ops.with_sort_key(emitter.sort_key);
+                    let count = emitter.sort_key as i32 + 1;
+                    ops.set_tag_count(emitter.target, &GameplayTag::new("A.B.C"), count);
+                });
+            })
+            .unwrap();
+        apply_gameplay_tag_ops(&mut world);
+
+        //排序键最大的操作最后执行
+        let count_container = world.get::<GameplayTagCountContainer>(target).unwrap();
+        assert_eq!(count_container.get_tag_count(&GameplayTag::new("A.B.C")), 8);
+    }
+}
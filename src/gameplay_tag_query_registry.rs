@@ -0,0 +1,331 @@
+use bevy::{
+    ecs::{
+        entity::{Entity, EntityHashSet},
+        event::EntityEvent,
+        lifecycle::Remove,
+        observer::On,
+        resource::Resource,
+        system::{Commands, Query, ResMut},
+    },
+    platform::collections::HashMap,
+};
+
+use crate::{
+    gameplay_tag_container::{GameplayTagContainer, GameplayTagQuery},
+    gameplay_tag_count_container::{GameplayTagCountContainer, GameplayTagCountNotification},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GameplayTagQueryId(usize);
+
+#[derive(Debug)]
+struct LiveGameplayTagQuery {
+    name: String,
+    query: GameplayTagQuery,
+    members: EntityHashSet,
+    //新注册的查询需要先对已有实体做一次完整评估
+    initialized: bool,
+}
+
+///
+/// Registry of named `GameplayTagQuery`s whose membership is kept up to date by the plugin.
+///
+/// Membership is only re-evaluated for an entity when one of its `GameplayTagCountContainer` tags
+/// is newly added or completely removed, so there is no per-frame polling. Whenever an entity starts
+/// or stops matching a query, `EnteredTagQuery` or `ExitedTagQuery` is triggered on that entity.
+///
+#[derive(Resource, Debug, Default)]
+pub struct GameplayTagQueryRegistry {
+    queries: Vec<LiveGameplayTagQuery>,
+    query_ids: HashMap<String, GameplayTagQueryId>,
+}
+
+impl GameplayTagQueryRegistry {
+    ///
+    /// Registers a named query. Registering a name that already exists replaces its query and
+    /// re-evaluates its membership from scratch on the next update.
+    ///
+    /// # Returns
+    /// * The `GameplayTagQueryId` that identifies the query in membership lookups and events.
+    ///
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        query: GameplayTagQuery,
+    ) -> GameplayTagQueryId {
+        let name = name.into();
+        if let Some(query_id) = self.query_ids.get(&name).copied() {
+            let live_query = &mut self.queries[query_id.0];
+            live_query.query = query;
+            live_query.initialized = false;
+            return query_id;
+        }
+
+        let query_id = GameplayTagQueryId(self.queries.len());
+        self.query_ids.insert(name.clone(), query_id);
+        self.queries.push(LiveGameplayTagQuery {
+            name,
+            query,
+            members: EntityHashSet::default(),
+            initialized: false,
+        });
+        query_id
+    }
+
+    pub fn get_query_id(&self, name: &str) -> Option<GameplayTagQueryId> {
+        self.query_ids.get(name).copied()
+    }
+
+    pub fn get_query_name(&self, query_id: GameplayTagQueryId) -> Option<&str> {
+        self.queries
+            .get(query_id.0)
+            .map(|live_query| live_query.name.as_str())
+    }
+
+    pub fn get_query(&self, query_id: GameplayTagQueryId) -> Option<&GameplayTagQuery> {
+        self.queries
+            .get(query_id.0)
+            .map(|live_query| &live_query.query)
+    }
+
+    ///
+    /// Returns every entity currently matching the query, or `None` if the id is unknown.
+    ///
+    pub fn get_members(&self, query_id: GameplayTagQueryId) -> Option<&EntityHashSet> {
+        self.queries
+            .get(query_id.0)
+            .map(|live_query| &live_query.members)
+    }
+
+    pub fn get_members_by_name(&self, name: &str) -> Option<&EntityHashSet> {
+        self.get_query_id(name)
+            .and_then(|query_id| self.get_members(query_id))
+    }
+
+    ///
+    /// Returns the members of a registered query equal to `query`, or `None` if there is none or
+    /// its membership has not been evaluated yet.
+    ///
+    pub fn get_members_of_query(&self, query: &GameplayTagQuery) -> Option<&EntityHashSet> {
+        self.queries
+            .iter()
+            .find(|live_query| live_query.initialized && live_query.query == *query)
+            .map(|live_query| &live_query.members)
+    }
+
+    pub fn is_member(&self, query_id: GameplayTagQueryId, entity: Entity) -> bool {
+        self.get_members(query_id)
+            .is_some_and(|members| members.contains(&entity))
+    }
+
+    ///
+    /// Re-evaluates every registered query for `entity`, triggering enter/exit events on changes.
+    /// Passing `None` as the container means the entity no longer carries any tags.
+    ///
+    pub fn evaluate_entity(
+        &mut self,
+        entity: Entity,
+        container: Option<&GameplayTagContainer>,
+        commands: &mut Commands,
+    ) {
+        for (index, live_query) in self.queries.iter_mut().enumerate() {
+            let matches = container.is_some_and(|tags| live_query.query.matches(tags));
+            Self::update_membership(
+                GameplayTagQueryId(index),
+                live_query,
+                entity,
+                matches,
+                commands,
+            );
+        }
+    }
+
+    fn update_membership(
+        query_id: GameplayTagQueryId,
+        live_query: &mut LiveGameplayTagQuery,
+        entity: Entity,
+        matches: bool,
+        commands: &mut Commands,
+    ) {
+        if matches {
+            if live_query.members.insert(entity) {
+                commands.trigger(EnteredTagQuery {
+                    entity,
+                    query_id,
+                    query_name: live_query.name.clone(),
+                });
+            }
+        } else if live_query.members.remove(&entity) {
+            commands.trigger(ExitedTagQuery {
+                entity,
+                query_id,
+                query_name: live_query.name.clone(),
+            });
+        }
+    }
+}
+
+/// Triggered on an entity when it starts matching a query registered in `GameplayTagQueryRegistry`.
+#[derive(EntityEvent, Debug)]
+pub struct EnteredTagQuery {
+    pub entity: Entity,
+    pub query_id: GameplayTagQueryId,
+    pub query_name: String,
+}
+
+/// Triggered on an entity when it stops matching a query registered in `GameplayTagQueryRegistry`.
+#[derive(EntityEvent, Debug)]
+pub struct ExitedTagQuery {
+    pub entity: Entity,
+    pub query_id: GameplayTagQueryId,
+    pub query_name: String,
+}
+
+pub(crate) fn update_tag_query_membership(
+    trigger: On<GameplayTagCountNotification>,
+    containers: Query<&GameplayTagCountContainer>,
+    mut registry: ResMut<GameplayTagQueryRegistry>,
+    mut commands: Commands,
+) {
+    let notification = trigger.event();
+    //只有标签新增或完全移除才可能改变查询结果
+    if !notification.has_presence_changes() {
+        return;
+    }
+    let container = containers
+        .get(notification.entity)
+        .ok()
+        .map(|count_container| count_container.get_explicit_tags());
+    registry.evaluate_entity(notification.entity, container, &mut commands);
+}
+
+pub(crate) fn remove_tag_query_membership(
+    trigger: On<Remove, GameplayTagCountContainer>,
+    mut registry: ResMut<GameplayTagQueryRegistry>,
+    mut commands: Commands,
+) {
+    registry.evaluate_entity(trigger.event().entity, None, &mut commands);
+}
+
+pub(crate) fn initialize_registered_tag_queries(
+    containers: Query<(Entity, &GameplayTagCountContainer)>,
+    mut registry: ResMut<GameplayTagQueryRegistry>,
+    mut commands: Commands,
+) {
+    if registry
+        .queries
+        .iter()
+        .all(|live_query| live_query.initialized)
+    {
+        return;
+    }
+
+    for (index, live_query) in registry.queries.iter_mut().enumerate() {
+        if live_query.initialized {
+            continue;
+        }
+        live_query.initialized = true;
+        let query_id = GameplayTagQueryId(index);
+        let stale_members: Vec<Entity> = live_query.members.iter().copied().collect();
+        for entity in stale_members {
+            if !containers.contains(entity) {
+                GameplayTagQueryRegistry::update_membership(
+                    query_id,
+                    live_query,
+                    entity,
+                    false,
+                    &mut commands,
+                );
+            }
+        }
+        for (entity, count_container) in containers.iter() {
+            let matches = live_query
+                .query
+                .matches(count_container.get_explicit_tags());
+            GameplayTagQueryRegistry::update_membership(
+                query_id,
+                live_query,
+                entity,
+                matches,
+                &mut commands,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::{DeferredWorld, World};
+
+    use super::*;
+    use crate::{
+        gameplay_tag::GameplayTag, gameplay_tag_commands::GameplayTagWorldExt,
+        gameplay_tags_manager::GameplayTagsManager,
+    };
+
+    #[derive(Resource, Default)]
+    struct MembershipEvents(Vec<(Entity, bool)>);
+
+    fn update_tag_count(world: &mut World, entity: Entity, tag_name: &str, count_delta: i32) {
+        world
+            .modify_gameplay_tags(entity, |tx| {
+                tx.update_tag_count(&GameplayTag::new(tag_name), count_delta)
+            })
+            .unwrap();
+        world.flush();
+    }
+
+    #[test]
+    fn entering_and_leaving_a_query_triggers_one_event_each() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<MembershipEvents>();
+        let mut tags = GameplayTagContainer::new();
+        tags.gameplay_tags.push(GameplayTag::new("A.B"));
+        let mut registry = GameplayTagQueryRegistry::default();
+        let query_id = registry.register("A.B", GameplayTagQuery::make_query_match_any_tags(&tags));
+        world.insert_resource(registry);
+        world.add_observer(update_tag_query_membership);
+        world.add_observer(|trigger: On<EnteredTagQuery>, mut world: DeferredWorld| {
+            let entity = trigger.event().entity;
+            world
+                .resource_mut::<MembershipEvents>()
+                .0
+                .push((entity, true));
+        });
+        world.add_observer(|trigger: On<ExitedTagQuery>, mut world: DeferredWorld| {
+            let entity = trigger.event().entity;
+            world
+                .resource_mut::<MembershipEvents>()
+                .0
+                .push((entity, false));
+        });
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+
+        update_tag_count(&mut world, entity, "A.B.C", 1);
+        update_tag_count(&mut world, entity, "A.B.D", 1);
+        update_tag_count(&mut world, entity, "A.C", 1);
+        assert!(
+            world
+                .resource::<GameplayTagQueryRegistry>()
+                .is_member(query_id, entity)
+        );
+        update_tag_count(&mut world, entity, "A.B.C", -1);
+        assert!(
+            world
+                .resource::<GameplayTagQueryRegistry>()
+                .is_member(query_id, entity)
+        );
+        update_tag_count(&mut world, entity, "A.B.D", -1);
+
+        assert!(
+            !world
+                .resource::<GameplayTagQueryRegistry>()
+                .is_member(query_id, entity)
+        );
+        assert_eq!(
+            world.resource::<MembershipEvents>().0,
+            vec![(entity, true), (entity, false)]
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use bevy::ecs::resource::Resource;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_container::{GameplayTagContainer, GameplayTagQuery},
+    gameplay_tag_count_container::GameplayTagCountContainer,
+    gameplay_tag_requirements::GameplayTagRequirements,
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+///
+/// One row of a `GameplayTagRelationshipMapping`: what an ability tagged with `ability_tag`
+/// (or one of its children) blocks, cancels and requires.
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GameplayTagRelationship {
+    pub ability_tag: GameplayTag,
+    /// Abilities with these tags are blocked while this ability is active.
+    #[serde(default)]
+    pub ability_tags_to_block: Vec<GameplayTag>,
+    /// Abilities with these tags are cancelled when this ability activates.
+    #[serde(default)]
+    pub ability_tags_to_cancel: Vec<GameplayTag>,
+    /// The owner needs all of these tags to activate this ability.
+    #[serde(default)]
+    pub activation_required_tags: Vec<GameplayTag>,
+    /// The owner must have none of these tags to activate this ability.
+    #[serde(default)]
+    pub activation_blocked_tags: Vec<GameplayTag>,
+}
+
+impl GameplayTagRelationship {
+    pub fn new(ability_tag: GameplayTag) -> Self {
+        GameplayTagRelationship {
+            ability_tag,
+            ability_tags_to_block: Vec::new(),
+            ability_tags_to_cancel: Vec::new(),
+            activation_required_tags: Vec::new(),
+            activation_blocked_tags: Vec::new(),
+        }
+    }
+}
+
+/// The tag sets that apply to an ability, gathered from every matching `GameplayTagRelationship`.
+#[derive(Debug, Clone, Default)]
+pub struct GameplayAbilityActivationTags {
+    pub ability_tags_to_block: GameplayTagContainer,
+    pub ability_tags_to_cancel: GameplayTagContainer,
+    pub activation_required_tags: GameplayTagContainer,
+    pub activation_blocked_tags: GameplayTagContainer,
+}
+
+impl GameplayAbilityActivationTags {
+    /// Returns the activation requirements: all required tags and none of the blocked tags.
+    pub fn get_activation_requirements(&self) -> GameplayTagRequirements {
+        GameplayTagRequirements::new(
+            self.activation_required_tags.clone(),
+            self.activation_blocked_tags.clone(),
+            GameplayTagQuery::new(),
+        )
+    }
+
+    ///
+    /// Returns `true` if the owner's tags meet the activation requirements. Blocking by other
+    /// active abilities is checked by `GameplayTagRelationshipMapping::can_activate`.
+    ///
+    pub fn can_activate(&self, owner: &GameplayTagCountContainer) -> bool {
+        let owner_tags = owner.get_explicit_tags();
+        owner_tags.has_all(&self.activation_required_tags)
+            && !owner_tags.has_any(&self.activation_blocked_tags)
+    }
+}
+
+///
+/// Data-driven table of ability tag relationships, in the spirit of Unreal's
+/// `AbilityTagRelationshipMapping`.
+///
+/// A relationship applies to an ability if the ability's tags match its `ability_tag`, so a row for
+/// `Ability.Skill` applies to every `Ability.Skill.*` ability. The tag sets of all matching rows are
+/// combined.
+///
+/// # Examples
+/// ```ignore
+/// let mapping = GameplayTagRelationshipMapping::from_json(r#"[
+///     { "ability_tag": "Ability.Skill", "activation_blocked_tags": ["Status.Stunned"] },
+///     { "ability_tag": "Ability.Skill.S1", "ability_tags_to_cancel": ["Ability.Skill.ChiXu"] }
+/// ]"#)?;
+/// if mapping.can_activate(&ability_tags, &owner_tags, &active_ability_tags, &tags_manager) { ... }
+/// ```
+///
+#[derive(Resource, Debug, Clone, Default)]
+pub struct GameplayTagRelationshipMapping {
+    relationships: Vec<GameplayTagRelationship>,
+}
+
+impl GameplayTagRelationshipMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the mapping from a JSON array of `GameplayTagRelationship` rows.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        Ok(GameplayTagRelationshipMapping {
+            relationships: serde_json::from_str(json)?,
+        })
+    }
+
+    pub fn add_relationship(&mut self, relationship: GameplayTagRelationship) {
+        self.relationships.push(relationship);
+    }
+
+    pub fn get_relationships(&self) -> &[GameplayTagRelationship] {
+        &self.relationships
+    }
+
+    ///
+    /// Collects the block, cancel, required and blocked tag sets of every relationship that applies
+    /// to an ability with `ability_tags`.
+    ///
+    pub fn get_activation_tags(
+        &self,
+        ability_tags: &GameplayTagContainer,
+        tags_manager: &GameplayTagsManager,
+    ) -> GameplayAbilityActivationTags {
+        let mut activation_tags = GameplayAbilityActivationTags::default();
+        for relationship in self.get_matching_relationships(ability_tags) {
+            Self::append(
+                &mut activation_tags.ability_tags_to_block,
+                &relationship.ability_tags_to_block,
+            );
+            Self::append(
+                &mut activation_tags.ability_tags_to_cancel,
+                &relationship.ability_tags_to_cancel,
+            );
+            Self::append(
+                &mut activation_tags.activation_required_tags,
+                &relationship.activation_required_tags,
+            );
+            Self::append(
+                &mut activation_tags.activation_blocked_tags,
+                &relationship.activation_blocked_tags,
+            );
+        }
+        //直接收集后统一整理，不经过add_tag，避免互斥分组把同组的标签互相移除
+        activation_tags
+            .ability_tags_to_block
+            .normalize(tags_manager);
+        activation_tags
+            .ability_tags_to_cancel
+            .normalize(tags_manager);
+        activation_tags
+            .activation_required_tags
+            .normalize(tags_manager);
+        activation_tags
+            .activation_blocked_tags
+            .normalize(tags_manager);
+        activation_tags
+    }
+
+    ///
+    /// Returns `true` if the ability with `ability_tags` can be activated by an owner with
+    /// `owner` tags according to this mapping: no active ability blocks it and the owner meets its
+    /// activation requirements. `active_ability_tags` holds the tags of all abilities that are
+    /// currently active on the owner.
+    ///
+    pub fn can_activate(
+        &self,
+        ability_tags: &GameplayTagContainer,
+        owner: &GameplayTagCountContainer,
+        active_ability_tags: &GameplayTagContainer,
+        tags_manager: &GameplayTagsManager,
+    ) -> bool {
+        !self.is_ability_blocked_by_tags(ability_tags, active_ability_tags)
+            && self
+                .get_activation_tags(ability_tags, tags_manager)
+                .can_activate(owner)
+    }
+
+    ///
+    /// Returns `true` if an active ability with `active_ability_tags` blocks an ability with
+    /// `ability_tags`.
+    ///
+    pub fn is_ability_blocked_by_tags(
+        &self,
+        ability_tags: &GameplayTagContainer,
+        active_ability_tags: &GameplayTagContainer,
+    ) -> bool {
+        self.get_matching_relationships(active_ability_tags)
+            .flat_map(|relationship| relationship.ability_tags_to_block.iter())
+            .any(|tag_to_block| ability_tags.has_tag(tag_to_block))
+    }
+
+    ///
+    /// Returns `true` if activating an ability with `action_tags` cancels an ability with
+    /// `ability_tags`.
+    ///
+    pub fn is_ability_cancelled_by_tags(
+        &self,
+        ability_tags: &GameplayTagContainer,
+        action_tags: &GameplayTagContainer,
+    ) -> bool {
+        self.get_matching_relationships(action_tags)
+            .flat_map(|relationship| relationship.ability_tags_to_cancel.iter())
+            .any(|tag_to_cancel| ability_tags.has_tag(tag_to_cancel))
+    }
+
+    fn get_matching_relationships<'a>(
+        &'a self,
+        ability_tags: &'a GameplayTagContainer,
+    ) -> impl Iterator<Item = &'a GameplayTagRelationship> + 'a {
+        self.relationships
+            .iter()
+            .filter(|relationship| ability_tags.has_tag(&relationship.ability_tag))
+    }
+
+    fn append(container: &mut GameplayTagContainer, tags: &[GameplayTag]) {
+        container.gameplay_tags.extend(tags.iter().cloned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::World;
+
+    use super::*;
+    use crate::gameplay_tag_commands::GameplayTagWorldExt;
+
+    fn tags(names: &[&str], tags_manager: &GameplayTagsManager) -> GameplayTagContainer {
+        let mut container = GameplayTagContainer::new();
+        for name in names {
+            container.add_tag(GameplayTag::new(name), tags_manager);
+        }
+        container
+    }
+
+    fn mapping() -> GameplayTagRelationshipMapping {
+        GameplayTagRelationshipMapping::from_json(
+            r#"[
+                { "ability_tag": "A", "ability_tags_to_block": ["D"] },
+                { "ability_tag": "A.B", "ability_tags_to_cancel": ["A.C"] },
+                { "ability_tag": "A.B.C", "activation_blocked_tags": ["Status.Damaged"] }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn matching_rows_combine_their_block_and_cancel_sets() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        let tags_manager = world.resource::<GameplayTagsManager>();
+        let mapping = mapping();
+
+        let activation_tags =
+            mapping.get_activation_tags(&tags(&["A.B.C"], tags_manager), tags_manager);
+        assert!(
+            activation_tags
+                .ability_tags_to_block
+                .has_tag_exact(&GameplayTag::new("D"))
+        );
+        assert!(
+            activation_tags
+                .ability_tags_to_cancel
+                .has_tag_exact(&GameplayTag::new("A.C"))
+        );
+        assert!(
+            activation_tags
+                .activation_blocked_tags
+                .has_tag_exact(&GameplayTag::new("Status.Damaged"))
+        );
+        let activation_tags =
+            mapping.get_activation_tags(&tags(&["A.C"], tags_manager), tags_manager);
+        assert!(activation_tags.ability_tags_to_cancel.is_empty());
+
+        let active_abilities = tags(&["A.B.D"], tags_manager);
+        assert!(
+            mapping.is_ability_blocked_by_tags(&tags(&["D.C"], tags_manager), &active_abilities)
+        );
+        assert!(
+            !mapping.is_ability_blocked_by_tags(&tags(&["A.C"], tags_manager), &active_abilities)
+        );
+        assert!(
+            mapping
+                .is_ability_cancelled_by_tags(&tags(&["A.C.B"], tags_manager), &active_abilities)
+        );
+        assert!(
+            !mapping
+                .is_ability_cancelled_by_tags(&tags(&["A.B.C"], tags_manager), &active_abilities)
+        );
+    }
+
+    #[test]
+    fn can_activate_checks_blockers_and_owner_tags() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        let owner = world.spawn(GameplayTagCountContainer::new()).id();
+        let mapping = mapping();
+        let can_activate = |world: &World, ability: &str, active: &[&str]| {
+            let tags_manager = world.resource::<GameplayTagsManager>();
+            mapping.can_activate(
+                &tags(&[ability], tags_manager),
+                world.get::<GameplayTagCountContainer>(owner).unwrap(),
+                &tags(active, tags_manager),
+                tags_manager,
+            )
+        };
+
+        assert!(can_activate(&world, "A.B.C", &[]));
+        assert!(!can_activate(&world, "D.C", &["A.B.D"]));
+        world.modify_gameplay_tags(owner, |tx| {
+            tx.update_tag_count(&GameplayTag::new("Status.Damaged"), 1)
+        });
+        assert!(!can_activate(&world, "A.B.C", &[]));
+        assert!(can_activate(&world, "A.B.D", &[]));
+    }
+}
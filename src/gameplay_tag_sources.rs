@@ -0,0 +1,156 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::{Entity, EntityHashSet},
+        lifecycle::Remove,
+        observer::On,
+        system::{Commands, Query, Res, SystemState},
+        world::World,
+    },
+    log::debug,
+};
+
+use crate::{
+    gameplay_tag_count_container::GameplayTagCountContainer,
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+///
+/// Marks an entity that grants tags to other entities through
+/// `GameplayTagCountContainer::update_tag_count_from_source`.
+///
+/// Inserted automatically on the first grant. Removing it, for example when an item is unequipped,
+/// or despawning the entity removes everything it still grants from all of its targets.
+///
+#[derive(Component, Debug, Default)]
+pub struct GameplayTagGrantor {
+    targets: EntityHashSet,
+}
+
+impl GameplayTagGrantor {
+    /// Returns the entities this source has granted tags to.
+    pub fn get_targets(&self) -> &EntityHashSet {
+        &self.targets
+    }
+}
+
+pub(crate) fn register_gameplay_tag_grant(commands: &mut Commands, source: Entity, target: Entity) {
+    commands.queue(move |world: &mut World| {
+        let Ok(mut source_entity) = world.get_entity_mut(source) else {
+            //来源在授予生效前就已销毁，立即移除它的贡献
+            debug!("标签来源实体 {} 已销毁，移除它授予的标签", source);
+            remove_source_from_targets(world, source, [target]);
+            return;
+        };
+        match source_entity.get_mut::<GameplayTagGrantor>() {
+            Some(mut grantor) => {
+                grantor.targets.insert(target);
+            }
+            None => {
+                let mut grantor = GameplayTagGrantor::default();
+                grantor.targets.insert(target);
+                source_entity.insert(grantor);
+            }
+        }
+    });
+}
+
+fn remove_source_from_targets(
+    world: &mut World,
+    source: Entity,
+    targets: impl IntoIterator<Item = Entity>,
+) {
+    let mut system_state = SystemState::<(
+        Query<&mut GameplayTagCountContainer>,
+        Res<GameplayTagsManager>,
+        Commands,
+    )>::new(world);
+    let (mut containers, tags_manager, mut commands) = system_state.get_mut(world);
+    for target in targets {
+        if let Ok(mut count_container) = containers.get_mut(target) {
+            count_container.remove_all_from_source(source, &tags_manager, &mut commands, target);
+        }
+    }
+    system_state.apply(world);
+}
+
+pub(crate) fn remove_gameplay_tag_grantor_contributions(
+    trigger: On<Remove, GameplayTagGrantor>,
+    grantors: Query<&GameplayTagGrantor>,
+    mut containers: Query<&mut GameplayTagCountContainer>,
+    tags_manager: Res<GameplayTagsManager>,
+    mut commands: Commands,
+) {
+    let source = trigger.event().entity;
+    let Ok(grantor) = grantors.get(source) else {
+        return;
+    };
+    for target in grantor.targets.iter().copied() {
+        if let Ok(mut count_container) = containers.get_mut(target) {
+            count_container.remove_all_from_source(source, &tags_manager, &mut commands, target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gameplay_tag::GameplayTag, gameplay_tag_commands::GameplayTagWorldExt};
+
+    fn setup_world() -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.add_observer(remove_gameplay_tag_grantor_contributions);
+        let target = world.spawn(GameplayTagCountContainer::new()).id();
+        (world, target)
+    }
+
+    fn grant(world: &mut World, target: Entity, source: Entity, count_delta: i32) {
+        world.modify_gameplay_tags(target, |tx| {
+            tx.update_tag_count_from_source(&GameplayTag::new("A.B.C"), count_delta, source)
+        });
+    }
+
+    fn tag_count(world: &World, target: Entity) -> i32 {
+        world
+            .get::<GameplayTagCountContainer>(target)
+            .unwrap()
+            .get_tag_count(&GameplayTag::new("A.B.C"))
+    }
+
+    #[test]
+    fn despawning_or_unequipping_a_grantor_removes_only_its_stacks() {
+        let (mut world, target) = setup_world();
+        let sword = world.spawn_empty().id();
+        let ring = world.spawn_empty().id();
+        grant(&mut world, target, sword, 2);
+        grant(&mut world, target, ring, 1);
+        world.modify_gameplay_tags(target, |tx| {
+            tx.update_tag_count(&GameplayTag::new("A.B.C"), 1)
+        });
+        assert_eq!(tag_count(&world, target), 4);
+        assert!(world.get::<GameplayTagGrantor>(sword).is_some());
+
+        world.despawn(sword);
+        assert_eq!(tag_count(&world, target), 2);
+        world.entity_mut(ring).remove::<GameplayTagGrantor>();
+        assert_eq!(tag_count(&world, target), 1);
+
+        let count_container = world.get::<GameplayTagCountContainer>(target).unwrap();
+        assert_eq!(count_container.get_sources().count(), 0);
+        assert_eq!(
+            count_container.get_unattributed_tag_count(&GameplayTag::new("A.B.C")),
+            1
+        );
+    }
+
+    #[test]
+    fn grant_from_despawned_source_is_removed_again() {
+        let (mut world, target) = setup_world();
+        let source = world.spawn_empty().id();
+        world.despawn(source);
+
+        grant(&mut world, target, source, 1);
+        assert_eq!(tag_count(&world, target), 0);
+    }
+}
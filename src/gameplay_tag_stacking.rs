@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::EntityEvent,
+        system::{Commands, Query, Res},
+    },
+    time::Time,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay_tag::GameplayTag, gameplay_tag_count_container::GameplayTagCountContainer,
+    gameplay_tag_timed_grants::TimedGameplayTags, gameplay_tags_manager::GameplayTagsManager,
+};
+
+/// What happens when adding stacks would exceed `GameplayTagStackingPolicy::max_stack_count`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum GameplayTagStackOverflowPolicy {
+    /// Add as many stacks as fit and drop the rest.
+    #[default]
+    Saturate,
+    /// Reject the whole addition.
+    Reject,
+}
+
+///
+/// Per-tag stacking rules applied by `GameplayTagCountContainer`.
+///
+/// Policies can be declared in the tag table under a row's `stacking` key, or set from code with
+/// `GameplayTagsManager::set_stacking_policy`. They apply to the explicit count of exactly that tag.
+///
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameplayTagStackingPolicy {
+    /// Maximum explicit count of the tag, `None` for unlimited.
+    pub max_stack_count: Option<i32>,
+    pub overflow_policy: GameplayTagStackOverflowPolicy,
+    /// Restart the timers of existing `TimedGameplayTags` grants when a new stack is granted.
+    pub refresh_duration_on_stack: bool,
+    /// Remove one stack every this many seconds, `None` to disable decay.
+    pub stack_decay_period: Option<f32>,
+    /// When a timed grant of the tag expires, remove all stacks instead of only the grant's own.
+    pub remove_all_stacks_on_expiry: bool,
+}
+
+impl GameplayTagStackingPolicy {
+    pub fn get_stack_decay_period(&self) -> Option<Duration> {
+        self.stack_decay_period
+            .filter(|period| *period > 0.0)
+            .map(Duration::from_secs_f32)
+    }
+}
+
+///
+/// Triggered on an entity when an addition to its `GameplayTagCountContainer` ran into the tag's
+/// `max_stack_count`, whether the addition was saturated or rejected.
+///
+#[derive(EntityEvent, Debug, Clone)]
+pub struct GameplayTagStackLimitReached {
+    pub entity: Entity,
+    pub tag: GameplayTag,
+    pub requested_delta: i32,
+    pub applied_delta: i32,
+    pub max_stack_count: i32,
+    pub overflow_policy: GameplayTagStackOverflowPolicy,
+}
+
+pub(crate) fn decay_gameplay_tag_stacks(
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &mut GameplayTagCountContainer,
+        Option<&mut TimedGameplayTags>,
+    )>,
+    tags_manager: Res<GameplayTagsManager>,
+    mut commands: Commands,
+) {
+    let delta = time.delta();
+    if delta.is_zero() || !tags_manager.has_stack_decay_policies() {
+        return;
+    }
+    for (entity, mut count_container, timed_tags) in query.iter_mut() {
+        if !count_container.has_stack_decay_timers() {
+            continue;
+        }
+        count_container.tick_stack_decay(delta, &tags_manager, &mut commands, entity);
+        //衰减掉的层数不能再由计时授予持有
+        if let Some(mut timed_tags) = timed_tags {
+            timed_tags.sync_grants(&count_container);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{system::RunSystemOnce, world::World};
+
+    use super::*;
+    use crate::gameplay_tag_commands::GameplayTagWorldExt;
+
+    fn advance_and_decay(world: &mut World, millis: u64) {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(millis));
+        world.run_system_once(decay_gameplay_tag_stacks).unwrap();
+    }
+
+    fn tag_count(world: &World, entity: Entity) -> i32 {
+        world
+            .get::<GameplayTagCountContainer>(entity)
+            .unwrap()
+            .get_tag_count(&GameplayTag::new("A.B.C"))
+    }
+
+    #[test]
+    fn stacks_decay_one_per_period_down_to_zero() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<Time>();
+        world
+            .resource_mut::<GameplayTagsManager>()
+            .set_stacking_policy(
+                GameplayTag::new("A.B.C"),
+                GameplayTagStackingPolicy {
+                    max_stack_count: Some(3),
+                    stack_decay_period: Some(1.0),
+                    ..Default::default()
+                },
+            );
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        world.modify_gameplay_tags(entity, |tx| {
+            tx.update_tag_count(&GameplayTag::new("A.B.C"), 5)
+        });
+        assert_eq!(tag_count(&world, entity), 3);
+
+        advance_and_decay(&mut world, 1500);
+        assert_eq!(tag_count(&world, entity), 2);
+        //上次剩余的0.5秒会累计到下一个周期
+        advance_and_decay(&mut world, 600);
+        assert_eq!(tag_count(&world, entity), 1);
+        advance_and_decay(&mut world, 3000);
+        assert_eq!(tag_count(&world, entity), 0);
+        assert!(
+            !world
+                .get::<GameplayTagCountContainer>(entity)
+                .unwrap()
+                .has_stack_decay_timers()
+        );
+    }
+}
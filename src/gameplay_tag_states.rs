@@ -0,0 +1,185 @@
+use bevy::{
+    ecs::{
+        entity::Entity,
+        observer::On,
+        query::With,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    log::warn,
+    state::state::{FreelyMutableState, NextState, State},
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_conditions::GlobalGameplayTags,
+    gameplay_tag_count_container::{GameplayTagCountContainer, GameplayTagCountNotification},
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+///
+/// Drives the state `S` from the tags on the `GlobalGameplayTags` entity.
+///
+/// Whenever one of the bound tags is added to or removed from the global
+/// `GameplayTagCountContainer`, the first binding whose tag is present becomes the next state.
+/// If none of the bound tags is present, the fallback state is used, if there is one.
+///
+#[derive(Resource, Debug)]
+pub struct GameplayTagStateBindings<S: FreelyMutableState> {
+    bindings: Vec<(GameplayTag, S)>,
+    fallback: Option<S>,
+}
+
+impl<S: FreelyMutableState> Default for GameplayTagStateBindings<S> {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+            fallback: None,
+        }
+    }
+}
+
+impl<S: FreelyMutableState> GameplayTagStateBindings<S> {
+    pub fn bind(&mut self, tag: GameplayTag, state: S) {
+        self.bindings.push((tag, state));
+    }
+
+    pub fn set_fallback(&mut self, state: Option<S>) {
+        self.fallback = state;
+    }
+
+    fn is_bound_tag(&self, tag: &GameplayTag) -> bool {
+        self.bindings.iter().any(|(bound_tag, _)| bound_tag == tag)
+    }
+
+    ///
+    /// Returns the state the tags of `count_container` map to, or `None` if none of the bound tags
+    /// is present and there is no fallback.
+    ///
+    pub fn resolve_state(&self, count_container: &GameplayTagCountContainer) -> Option<&S> {
+        self.bindings
+            .iter()
+            .find(|(tag, _)| count_container.has_matching_gameplay_tag(tag))
+            .map(|(_, state)| state)
+            .or(self.fallback.as_ref())
+    }
+}
+
+pub(crate) fn update_state_from_global_tags<S: FreelyMutableState>(
+    trigger: On<GameplayTagCountNotification>,
+    bindings: Res<GameplayTagStateBindings<S>>,
+    global_tags: Query<&GameplayTagCountContainer, With<GlobalGameplayTags>>,
+    current_state: Option<Res<State<S>>>,
+    mut next_state: ResMut<NextState<S>>,
+) {
+    let notification = trigger.event();
+    if !notification
+        .presence_changes()
+        .any(|change| bindings.is_bound_tag(&change.tag))
+    {
+        return;
+    }
+    let Ok(count_container) = global_tags.get(notification.entity) else {
+        return;
+    };
+    if let Some(state) = bindings.resolve_state(count_container)
+        && current_state.is_none_or(|current_state| current_state.get() != state)
+    {
+        next_state.set(state.clone());
+    }
+}
+
+type GlobalTagCountContainersMut<'w, 's> =
+    Query<'w, 's, (Entity, &'static mut GameplayTagCountContainer), With<GlobalGameplayTags>>;
+
+///
+/// Returns a system that updates `tag` by `count_delta` on the `GlobalGameplayTags` entity.
+/// Used for the `OnEnter` / `OnExit` schedules of states that grant tags.
+///
+pub(crate) fn update_global_tag_count(
+    tag: GameplayTag,
+    count_delta: i32,
+) -> impl FnMut(GlobalTagCountContainersMut, Res<GameplayTagsManager>, Commands) {
+    move |mut global_tags: GlobalTagCountContainersMut,
+          tags_manager: Res<GameplayTagsManager>,
+          mut commands: Commands| {
+        match global_tags.single_mut() {
+            Ok((entity, mut count_container)) => {
+                count_container.update_tag_count(
+                    &tag,
+                    count_delta,
+                    &tags_manager,
+                    &mut commands,
+                    entity,
+                );
+            }
+            Err(_) => {
+                warn!(
+                    "需要唯一一个带有 GlobalGameplayTags 和 GameplayTagCountContainer 的实体来更新标签：{}",
+                    tag.get_tag_name()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::world::World,
+        state::state::{NextState, States},
+    };
+
+    use super::*;
+    use crate::gameplay_tag_commands::GameplayTagWorldExt;
+
+    #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+    enum Phase {
+        #[default]
+        Idle,
+        Combat,
+    }
+
+    fn update_tag_count(world: &mut World, entity: Entity, tag_name: &str, count_delta: i32) {
+        world
+            .modify_gameplay_tags(entity, |tx| {
+                tx.update_tag_count(&GameplayTag::new(tag_name), count_delta)
+            })
+            .unwrap();
+        world.flush();
+    }
+
+    fn take_next_state(world: &mut World) -> Option<Phase> {
+        match std::mem::take(&mut *world.resource_mut::<NextState<Phase>>()) {
+            NextState::Pending(state) => Some(state),
+            NextState::Unchanged => None,
+        }
+    }
+
+    #[test]
+    fn bound_tag_on_global_entity_drives_state() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.insert_resource(State::new(Phase::Idle));
+        world.init_resource::<NextState<Phase>>();
+        let mut bindings = GameplayTagStateBindings::<Phase>::default();
+        bindings.bind(GameplayTag::new("A.B"), Phase::Combat);
+        bindings.set_fallback(Some(Phase::Idle));
+        world.insert_resource(bindings);
+        world.add_observer(update_state_from_global_tags::<Phase>);
+        let global = world
+            .spawn((GlobalGameplayTags, GameplayTagCountContainer::new()))
+            .id();
+        let other = world.spawn(GameplayTagCountContainer::new()).id();
+
+        update_tag_count(&mut world, other, "A.B.C", 1);
+        assert_eq!(take_next_state(&mut world), None);
+
+        update_tag_count(&mut world, global, "A.B.C", 1);
+        assert_eq!(take_next_state(&mut world), Some(Phase::Combat));
+        world.insert_resource(State::new(Phase::Combat));
+
+        update_tag_count(&mut world, global, "A.B.C", -1);
+        assert_eq!(take_next_state(&mut world), Some(Phase::Idle));
+    }
+}
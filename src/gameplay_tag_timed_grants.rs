@@ -0,0 +1,489 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        observer::On,
+        system::{Commands, Query, Res},
+    },
+    time::Time,
+};
+
+use crate::{
+    gameplay_tag::GameplayTag,
+    gameplay_tag_count_container::{
+        GameplayTagCountContainer, GameplayTagCountNotification, GameplayTagEventType,
+    },
+    gameplay_tags_manager::GameplayTagsManager,
+};
+
+/// Identifies a single timed grant inside a `TimedGameplayTags` component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimedGameplayTagHandle(u64);
+
+#[derive(Debug, Clone)]
+pub struct TimedGameplayTagGrant {
+    handle: TimedGameplayTagHandle,
+    tag: GameplayTag,
+    count: i32,
+    duration: Duration,
+    remaining: Duration,
+}
+
+impl TimedGameplayTagGrant {
+    pub fn get_handle(&self) -> TimedGameplayTagHandle {
+        self.handle
+    }
+
+    pub fn get_tag(&self) -> &GameplayTag {
+        &self.tag
+    }
+
+    pub fn get_count(&self) -> i32 {
+        self.count
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn get_remaining(&self) -> Duration {
+        self.remaining
+    }
+}
+
+///
+/// Companion component of `GameplayTagCountContainer` holding tag grants that expire on their own.
+///
+/// Each grant adds `count` to its tag when it is created, and the plugin removes that count again
+/// once the grant's duration has elapsed. Durations are ticked with the default `Time` clock, so
+/// pausing or scaling virtual time also pauses or scales the grants.
+///
+/// Grants only own stacks that are still present: when stacks of a granted tag are removed by
+/// other means, such as stack decay, an exclusive sibling or `update_tag_count`, the grants closest
+/// to expiring give up those stacks, so expiring later never removes stacks added by someone else.
+///
+#[derive(Component, Debug, Default, Clone)]
+#[require(GameplayTagCountContainer)]
+pub struct TimedGameplayTags {
+    grants: Vec<TimedGameplayTagGrant>,
+    next_handle: u64,
+}
+
+impl TimedGameplayTags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Adds `count` to `tag` on `count_container` for `duration`.
+    ///
+    /// The tag's `GameplayTagStackingPolicy` is respected: the grant only holds the stacks that were
+    /// actually added, and existing grants of the tag are refreshed if the policy asks for it.
+    ///
+    /// # Returns
+    /// * The handle of the new grant, or `None` if nothing was granted, either because `count` is
+    ///   not positive or because the stacking policy rejected the addition.
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_timed_tag(
+        &mut self,
+        tag: &GameplayTag,
+        count: i32,
+        duration: Duration,
+        count_container: &mut GameplayTagCountContainer,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) -> Option<TimedGameplayTagHandle> {
+        if count <= 0 {
+            return None;
+        }
+        self.sync_grants(count_container);
+        let result = count_container.update_tag_count(tag, count, tags_manager, commands, entity);
+        let count = result.get_applied_delta();
+        if count <= 0 {
+            return None;
+        }
+        if tags_manager
+            .get_stacking_policy(tag)
+            .is_some_and(|policy| policy.refresh_duration_on_stack)
+        {
+            self.refresh_tag(tag);
+        }
+
+        let handle = TimedGameplayTagHandle(self.next_handle);
+        self.next_handle += 1;
+        self.grants.push(TimedGameplayTagGrant {
+            handle,
+            tag: tag.clone(),
+            count,
+            duration,
+            remaining: duration,
+        });
+        Some(handle)
+    }
+
+    ///
+    /// Removes a grant before it expires, taking its count off `count_container`.
+    ///
+    /// # Returns
+    /// * `true` if the grant existed and was removed.
+    ///
+    pub fn remove_timed_tag(
+        &mut self,
+        handle: TimedGameplayTagHandle,
+        count_container: &mut GameplayTagCountContainer,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) -> bool {
+        self.sync_grants(count_container);
+        match self.find_grant_index(handle) {
+            Some(index) => {
+                let grant = self.grants.remove(index);
+                count_container.update_tag_count(
+                    &grant.tag,
+                    -grant.count,
+                    tags_manager,
+                    commands,
+                    entity,
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get_grant(&self, handle: TimedGameplayTagHandle) -> Option<&TimedGameplayTagGrant> {
+        self.grants.iter().find(|grant| grant.handle == handle)
+    }
+
+    /// Returns all active grants, in the order they were added.
+    pub fn get_grants(&self) -> &[TimedGameplayTagGrant] {
+        &self.grants
+    }
+
+    pub fn get_remaining_time(&self, handle: TimedGameplayTagHandle) -> Option<Duration> {
+        self.get_grant(handle).map(|grant| grant.remaining)
+    }
+
+    /// Returns the longest remaining time of all grants for exactly `tag`.
+    pub fn get_tag_remaining_time(&self, tag: &GameplayTag) -> Option<Duration> {
+        self.grants
+            .iter()
+            .filter(|grant| &grant.tag == tag)
+            .map(|grant| grant.remaining)
+            .max()
+    }
+
+    /// Restarts the grant's timer from its full duration.
+    pub fn refresh(&mut self, handle: TimedGameplayTagHandle) -> bool {
+        match self.find_grant_mut(handle) {
+            Some(grant) => {
+                grant.remaining = grant.duration;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Restarts the timers of every grant for exactly `tag`.
+    pub fn refresh_tag(&mut self, tag: &GameplayTag) {
+        for grant in self.grants.iter_mut().filter(|grant| &grant.tag == tag) {
+            grant.remaining = grant.duration;
+        }
+    }
+
+    /// Adds `extra_time` to the remaining time of the grant.
+    pub fn extend(&mut self, handle: TimedGameplayTagHandle, extra_time: Duration) -> bool {
+        match self.find_grant_mut(handle) {
+            Some(grant) => {
+                grant.remaining += extra_time;
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Advances all grants by `delta` and removes the count of every grant that expired.
+    /// This is called by the plugin every frame; call it manually only for custom clocks.
+    ///
+    pub fn tick(
+        &mut self,
+        delta: Duration,
+        count_container: &mut GameplayTagCountContainer,
+        tags_manager: &GameplayTagsManager,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        self.sync_grants(count_container);
+        let mut expired_grants = Vec::new();
+        self.grants.retain_mut(|grant| {
+            grant.remaining = grant.remaining.saturating_sub(delta);
+            if grant.remaining.is_zero() {
+                expired_grants.push((grant.tag.clone(), grant.count));
+                false
+            } else {
+                true
+            }
+        });
+        for (tag, count) in expired_grants {
+            if tags_manager
+                .get_stacking_policy(&tag)
+                .is_some_and(|policy| policy.remove_all_stacks_on_expiry)
+            {
+                //该标签的所有层数都已移除，其它计时授予也随之失效
+                self.grants.retain(|grant| grant.tag != tag);
+                count_container.set_tag_count(&tag, 0, tags_manager, commands, entity);
+            } else {
+                count_container.update_tag_count(&tag, -count, tags_manager, commands, entity);
+            }
+        }
+    }
+
+    //层数可能被计时授予之外的操作移除（衰减、互斥分组、匿名移除），授予的层数不能超过实际剩余的匿名层数，
+    //否则到期时会移除属于别人的层数。优先扣除剩余时间最短的授予
+    pub(crate) fn sync_grants(&mut self, count_container: &GameplayTagCountContainer) {
+        let mut tags: Vec<GameplayTag> =
+            self.grants.iter().map(|grant| grant.tag.clone()).collect();
+        tags.sort();
+        tags.dedup();
+        for tag in tags {
+            let granted_count: i32 = self
+                .grants
+                .iter()
+                .filter(|grant| grant.tag == tag)
+                .map(|grant| grant.count)
+                .sum();
+            let mut excess =
+                granted_count - count_container.get_unattributed_tag_count(&tag).max(0);
+            if excess <= 0 {
+                continue;
+            }
+            let mut grant_indices: Vec<usize> = (0..self.grants.len())
+                .filter(|index| self.grants[*index].tag == tag)
+                .collect();
+            grant_indices
+                .sort_by_key(|index| (self.grants[*index].remaining, self.grants[*index].handle.0));
+            for index in grant_indices {
+                let grant = &mut self.grants[index];
+                let trimmed = grant.count.min(excess);
+                grant.count -= trimmed;
+                excess -= trimmed;
+                if excess <= 0 {
+                    break;
+                }
+            }
+        }
+        self.grants.retain(|grant| grant.count > 0);
+    }
+
+    fn find_grant_index(&self, handle: TimedGameplayTagHandle) -> Option<usize> {
+        self.grants.iter().position(|grant| grant.handle == handle)
+    }
+
+    fn find_grant_mut(
+        &mut self,
+        handle: TimedGameplayTagHandle,
+    ) -> Option<&mut TimedGameplayTagGrant> {
+        self.grants.iter_mut().find(|grant| grant.handle == handle)
+    }
+}
+
+//互斥分组或匿名移除显式标签后，立即让计时授予放弃已经不存在的层数
+pub(crate) fn sync_timed_gameplay_tags_on_removed(
+    trigger: On<GameplayTagCountNotification>,
+    mut query: Query<(&GameplayTagCountContainer, &mut TimedGameplayTags)>,
+) {
+    let notification = trigger.event();
+    let mut removed_tags = notification.changes.iter().filter(|change| {
+        change.event_type == GameplayTagEventType::AnyCountChanged
+            && change.is_explicit
+            && change.delta < 0
+    });
+    if let Ok((count_container, mut timed_tags)) = query.get_mut(notification.entity)
+        && removed_tags.any(|change| {
+            timed_tags
+                .grants
+                .iter()
+                .any(|grant| grant.tag == change.tag)
+        })
+    {
+        timed_tags.sync_grants(count_container);
+    }
+}
+
+pub(crate) fn tick_timed_gameplay_tags(
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &mut TimedGameplayTags,
+        &mut GameplayTagCountContainer,
+    )>,
+    tags_manager: Res<GameplayTagsManager>,
+    mut commands: Commands,
+) {
+    let delta = time.delta();
+    if delta.is_zero() {
+        return;
+    }
+    for (entity, mut timed_tags, mut count_container) in query.iter_mut() {
+        if timed_tags.grants.is_empty() {
+            continue;
+        }
+        timed_tags.tick(
+            delta,
+            &mut count_container,
+            &tags_manager,
+            &mut commands,
+            entity,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{world::CommandQueue, world::World};
+
+    use super::*;
+
+    fn with_timed_tags<R>(
+        world: &mut World,
+        entity: Entity,
+        modify: impl FnOnce(
+            &mut TimedGameplayTags,
+            &mut GameplayTagCountContainer,
+            &GameplayTagsManager,
+            &mut Commands,
+        ) -> R,
+    ) -> R {
+        let (mut timed_tags, mut count_container) = world
+            .entity_mut(entity)
+            .take::<(TimedGameplayTags, GameplayTagCountContainer)>()
+            .unwrap();
+        let mut queue = CommandQueue::default();
+        let result = {
+            let mut commands = Commands::new(&mut queue, world);
+            modify(
+                &mut timed_tags,
+                &mut count_container,
+                world.resource::<GameplayTagsManager>(),
+                &mut commands,
+            )
+        };
+        world
+            .entity_mut(entity)
+            .insert((timed_tags, count_container));
+        queue.apply(world);
+        result
+    }
+
+    fn tick(world: &mut World, entity: Entity, millis: u64) {
+        with_timed_tags(
+            world,
+            entity,
+            |timed_tags, count_container, tags_manager, commands| {
+                timed_tags.tick(
+                    Duration::from_millis(millis),
+                    count_container,
+                    tags_manager,
+                    commands,
+                    entity,
+                );
+            },
+        );
+    }
+
+    fn tag_count(world: &World, entity: Entity) -> i32 {
+        world
+            .get::<GameplayTagCountContainer>(entity)
+            .unwrap()
+            .get_tag_count(&GameplayTag::new("A.B.C"))
+    }
+
+    #[test]
+    fn grant_expires_after_its_duration_and_refresh_restarts_it() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        let entity = world.spawn(TimedGameplayTags::new()).id();
+        let handle = with_timed_tags(
+            &mut world,
+            entity,
+            |timed_tags, count_container, tags_manager, commands| {
+                timed_tags.add_timed_tag(
+                    &GameplayTag::new("A.B.C"),
+                    2,
+                    Duration::from_secs(1),
+                    count_container,
+                    tags_manager,
+                    commands,
+                    entity,
+                )
+            },
+        )
+        .unwrap();
+        assert_eq!(tag_count(&world, entity), 2);
+
+        tick(&mut world, entity, 600);
+        assert_eq!(tag_count(&world, entity), 2);
+        assert!(
+            world
+                .get_mut::<TimedGameplayTags>(entity)
+                .unwrap()
+                .refresh(handle)
+        );
+        tick(&mut world, entity, 600);
+        assert_eq!(tag_count(&world, entity), 2);
+
+        tick(&mut world, entity, 500);
+        assert_eq!(tag_count(&world, entity), 0);
+        let timed_tags = world.get::<TimedGameplayTags>(entity).unwrap();
+        assert!(timed_tags.get_grant(handle).is_none());
+    }
+
+    #[test]
+    fn expiry_only_removes_stacks_the_grant_still_owns() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.add_observer(sync_timed_gameplay_tags_on_removed);
+        let entity = world.spawn(TimedGameplayTags::new()).id();
+        let tag = GameplayTag::new("A.B.C");
+        with_timed_tags(
+            &mut world,
+            entity,
+            |timed_tags, count_container, tags_manager, commands| {
+                timed_tags.add_timed_tag(
+                    &tag,
+                    2,
+                    Duration::from_secs(1),
+                    count_container,
+                    tags_manager,
+                    commands,
+                    entity,
+                );
+            },
+        );
+        //授予的一层被别人移除后，再匿名添加的一层不属于计时授予
+        with_timed_tags(
+            &mut world,
+            entity,
+            |_, count_container, tags_manager, commands| {
+                count_container.update_tag_count(&tag, -1, tags_manager, commands, entity);
+            },
+        );
+        with_timed_tags(
+            &mut world,
+            entity,
+            |_, count_container, tags_manager, commands| {
+                count_container.update_tag_count(&tag, 1, tags_manager, commands, entity);
+            },
+        );
+        assert_eq!(tag_count(&world, entity), 2);
+
+        tick(&mut world, entity, 1000);
+        assert_eq!(tag_count(&world, entity), 1);
+    }
+}
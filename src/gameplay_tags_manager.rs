@@ -1,15 +1,28 @@
 use crate::gameplay_tag::GameplayTag;
 use crate::gameplay_tag_container::GameplayTagContainer;
-use bevy::platform::collections::HashMap;
+use crate::gameplay_tag_stacking::GameplayTagStackingPolicy;
+use bevy::log::warn;
+use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::{ChildOf, Children, Component, Entity, FromWorld, Name, Resource, World};
 use serde::{Deserialize, Serialize};
 use std::fs::read_to_string;
+use std::ops::Deref;
+use std::sync::Arc;
 use string_cache::DefaultAtom as FName;
 
-#[derive(Resource, Debug)]
+#[derive(Resource, Debug, Clone)]
 pub struct GameplayTagsManager {
     pub root: Entity,
     pub tag_map: HashMap<GameplayTag, GameplayTagContainer>,
+    stacking_policies: HashMap<GameplayTag, GameplayTagStackingPolicy>,
+    //阻挡标签 -> 被阻挡的标签，阻挡标签存在时无法添加被阻挡的标签（包括其子标签）
+    tag_block_rules: HashMap<GameplayTag, Vec<GameplayTag>>,
+    //抑制标签 -> 被抑制的标签，抑制标签存在时被抑制的标签（包括其子标签）保留计数但对匹配不可见
+    tag_inhibition_rules: HashMap<GameplayTag, Vec<GameplayTag>>,
+    //互斥分组的父标签，同一时间只能存在其中一个子标签分支
+    exclusive_groups: HashSet<GameplayTag>,
+    //标签 -> 它在层级之外额外隐含的标签，匹配时视为同时拥有这些标签及其父标签
+    tag_implications: HashMap<GameplayTag, Vec<GameplayTag>>,
 }
 
 impl FromWorld for GameplayTagsManager {
@@ -36,10 +49,48 @@ impl FromWorld for GameplayTagsManager {
         let mut gameplay_tags_manager = GameplayTagsManager {
             root,
             tag_map: HashMap::new(),
+            stacking_policies: HashMap::new(),
+            tag_block_rules: HashMap::new(),
+            tag_inhibition_rules: HashMap::new(),
+            exclusive_groups: HashSet::new(),
+            tag_implications: HashMap::new(),
         };
 
+        //隐含关系会影响标签的完整容器，所以要在创建标签节点之前全部注册
+        let mut tag_names = Vec::new();
         for data_row in tag_data_table {
-            gameplay_tags_manager.add_tag_node(data_row.tag_name, world);
+            if let Some(stacking_policy) = data_row.stacking {
+                gameplay_tags_manager
+                    .set_stacking_policy(GameplayTag::new(&data_row.tag_name), stacking_policy);
+            }
+            for blocked_tag_name in data_row.blocks.iter() {
+                gameplay_tags_manager.add_tag_block(
+                    GameplayTag::new(&data_row.tag_name),
+                    GameplayTag::new(blocked_tag_name),
+                );
+            }
+            if data_row.exclusive {
+                gameplay_tags_manager
+                    .set_exclusive_group(GameplayTag::new(&data_row.tag_name), true);
+            }
+            for inhibited_tag_name in data_row.inhibits.iter() {
+                gameplay_tags_manager.add_tag_inhibition(
+                    GameplayTag::new(&data_row.tag_name),
+                    GameplayTag::new(inhibited_tag_name),
+                );
+            }
+            for implied_tag_name in data_row.implies.iter() {
+                if gameplay_tags_manager.insert_tag_implication(
+                    GameplayTag::new(&data_row.tag_name),
+                    GameplayTag::new(implied_tag_name),
+                ) {
+                    tag_names.push(implied_tag_name.clone());
+                }
+            }
+            tag_names.push(data_row.tag_name);
+        }
+        for tag_name in tag_names {
+            gameplay_tags_manager.add_tag_node(tag_name, world);
         }
 
         gameplay_tags_manager
@@ -60,6 +111,264 @@ impl GameplayTagsManager {
         }
     }
 
+    pub fn get_stacking_policy(&self, tag: &GameplayTag) -> Option<&GameplayTagStackingPolicy> {
+        self.stacking_policies.get(tag)
+    }
+
+    /// Sets the stacking policy of exactly `tag`, replacing the one from the tag table if any.
+    pub fn set_stacking_policy(&mut self, tag: GameplayTag, policy: GameplayTagStackingPolicy) {
+        self.stacking_policies.insert(tag, policy);
+    }
+
+    pub fn remove_stacking_policy(&mut self, tag: &GameplayTag) -> Option<GameplayTagStackingPolicy> {
+        self.stacking_policies.remove(tag)
+    }
+
+    pub(crate) fn has_stack_decay_policies(&self) -> bool {
+        self.stacking_policies
+            .values()
+            .any(|policy| policy.get_stack_decay_period().is_some())
+    }
+
+    ///
+    /// Prevents `blocked` and its children from being added to a `GameplayTagCountContainer` while
+    /// the container has `blocker` or one of its children.
+    ///
+    pub fn add_tag_block(&mut self, blocker: GameplayTag, blocked: GameplayTag) {
+        let blocked_tags = self.tag_block_rules.entry(blocker).or_default();
+        if !blocked_tags.contains(&blocked) {
+            blocked_tags.push(blocked);
+        }
+    }
+
+    pub fn remove_tag_block(&mut self, blocker: &GameplayTag, blocked: &GameplayTag) -> bool {
+        let Some(blocked_tags) = self.tag_block_rules.get_mut(blocker) else {
+            return false;
+        };
+        let Some(index) = blocked_tags.iter().position(|tag| tag == blocked) else {
+            return false;
+        };
+        blocked_tags.remove(index);
+        if blocked_tags.is_empty() {
+            self.tag_block_rules.remove(blocker);
+        }
+        true
+    }
+
+    /// Returns the tags blocked while `blocker` is present.
+    pub fn get_blocked_tags(&self, blocker: &GameplayTag) -> &[GameplayTag] {
+        self.tag_block_rules
+            .get(blocker)
+            .map(|blocked_tags| blocked_tags.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn get_tag_block_rules(
+        &self,
+    ) -> impl Iterator<Item = (&GameplayTag, &Vec<GameplayTag>)> {
+        self.tag_block_rules.iter()
+    }
+
+    ///
+    /// Hides `inhibited` and its children from matching on a `GameplayTagCountContainer` while the
+    /// container has `inhibitor` or one of its children. Unlike a block, the inhibited tags keep
+    /// their counts and become visible again once the inhibitor is gone.
+    ///
+    pub fn add_tag_inhibition(&mut self, inhibitor: GameplayTag, inhibited: GameplayTag) {
+        let inhibited_tags = self.tag_inhibition_rules.entry(inhibitor).or_default();
+        if !inhibited_tags.contains(&inhibited) {
+            inhibited_tags.push(inhibited);
+        }
+    }
+
+    pub fn remove_tag_inhibition(&mut self, inhibitor: &GameplayTag, inhibited: &GameplayTag) -> bool {
+        let Some(inhibited_tags) = self.tag_inhibition_rules.get_mut(inhibitor) else {
+            return false;
+        };
+        let Some(index) = inhibited_tags.iter().position(|tag| tag == inhibited) else {
+            return false;
+        };
+        inhibited_tags.remove(index);
+        if inhibited_tags.is_empty() {
+            self.tag_inhibition_rules.remove(inhibitor);
+        }
+        true
+    }
+
+    /// Returns the tags inhibited while `inhibitor` is present.
+    pub fn get_inhibited_tags(&self, inhibitor: &GameplayTag) -> &[GameplayTag] {
+        self.tag_inhibition_rules
+            .get(inhibitor)
+            .map(|inhibited_tags| inhibited_tags.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn get_tag_inhibition_rules(
+        &self,
+    ) -> impl Iterator<Item = (&GameplayTag, &Vec<GameplayTag>)> {
+        self.tag_inhibition_rules.iter()
+    }
+
+    pub(crate) fn has_tag_inhibitions(&self) -> bool {
+        !self.tag_inhibition_rules.is_empty()
+    }
+
+    ///
+    /// Marks `group` as an exclusive group: its children are mutually exclusive, so adding one of
+    /// them (or one of its descendants) to a `GameplayTagCountContainer`, or to a
+    /// `GameplayTagContainer` with `add_tag`, removes the others.
+    ///
+    pub fn set_exclusive_group(&mut self, group: GameplayTag, exclusive: bool) {
+        if exclusive {
+            self.exclusive_groups.insert(group);
+        } else {
+            self.exclusive_groups.remove(&group);
+        }
+    }
+
+    pub fn is_exclusive_group(&self, tag: &GameplayTag) -> bool {
+        self.exclusive_groups.contains(tag)
+    }
+
+    ///
+    /// Returns `true` if `tag` and `other` lie below different children of a common exclusive group,
+    /// e.g. `Stance.Crouch` and `Stance.Prone` when `Stance` is exclusive.
+    ///
+    pub fn are_exclusive_siblings(&self, tag: &GameplayTag, other: &GameplayTag) -> bool {
+        if tag == other || self.exclusive_groups.is_empty() {
+            return false;
+        }
+        let Some(complete_container) = self.get_single_tag_container(tag) else {
+            return false;
+        };
+        complete_container
+            .get_parent_tags()
+            .iter()
+            .filter(|parent_tag| self.exclusive_groups.contains(*parent_tag))
+            .any(|group| {
+                match (
+                    Self::get_exclusive_option(group, tag),
+                    Self::get_exclusive_option(group, other),
+                ) {
+                    (Some(option), Some(other_option)) => option != other_option,
+                    _ => false,
+                }
+            })
+    }
+
+    pub(crate) fn has_exclusive_groups(&self) -> bool {
+        !self.exclusive_groups.is_empty()
+    }
+
+    ///
+    /// Declares that `tag` (and its children) also counts as `implied` and the parents of `implied`
+    /// for matching, e.g. `Element.Steam` implying `Element.Water` and `Element.Fire`.
+    ///
+    /// The tag hierarchy stays a tree; implications are only folded into the implicit tags of each
+    /// tag. Containers built before the call keep their old implicit tags, so implications should
+    /// be declared before tags are used.
+    ///
+    /// Since implied tags are stored as implicit parents, everything built on parent tags treats
+    /// the implying tag like a descendant of the implied tag:
+    /// * `request_gameplay_tag_parents` and `matches_tag` include the implied tags.
+    /// * A `GameplayTagCountContainer` counts the implied tags like parents, and triggers their
+    ///   events with `is_explicit` set to `false` and the implying tag as `cause_tag`.
+    /// * `observe_tag_subtree`, `any_entity_has_tag` and tag queries on an implied tag fire for, or
+    ///   match, the implying tag.
+    /// * Exclusive groups only look at the tag's own hierarchy, so implying a member of a group
+    ///   does not replace its siblings.
+    ///
+    /// # Returns
+    /// * `false` if the implication would create a cycle, in which case it is ignored.
+    ///
+    pub fn add_tag_implication(&mut self, tag: GameplayTag, implied: GameplayTag) -> bool {
+        if !self.insert_tag_implication(tag, implied) {
+            return false;
+        }
+        let tags: Vec<GameplayTag> = self.tag_map.keys().cloned().collect();
+        for tag in tags {
+            let complete_container = self.build_complete_tag_container(tag.get_tag_name());
+            self.tag_map.insert(tag, complete_container);
+        }
+        true
+    }
+
+    /// Returns the tags directly implied by exactly `tag`.
+    pub fn get_implied_tags(&self, tag: &GameplayTag) -> &[GameplayTag] {
+        self.tag_implications
+            .get(tag)
+            .map(|implied_tags| implied_tags.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn insert_tag_implication(&mut self, tag: GameplayTag, implied: GameplayTag) -> bool {
+        //从被隐含的标签出发能回到自身，说明形成了循环
+        let mut reachable_tags = Self::split_tag_hierarchy(implied.get_tag_name());
+        reachable_tags.extend(self.collect_implied_tags(&implied));
+        if reachable_tags.contains(&tag) {
+            warn!(
+                "标签隐含关系 {} -> {} 会形成循环，已忽略",
+                tag.get_tag_name(),
+                implied.get_tag_name()
+            );
+            return false;
+        }
+        let implied_tags = self.tag_implications.entry(tag).or_default();
+        if !implied_tags.contains(&implied) {
+            implied_tags.push(implied);
+        }
+        true
+    }
+
+    ///
+    /// Collects every tag implied by `tag` or one of its parents, transitively, together with the
+    /// parents of the implied tags.
+    ///
+    fn collect_implied_tags(&self, tag: &GameplayTag) -> Vec<GameplayTag> {
+        let mut implied_tags = Vec::new();
+        if self.tag_implications.is_empty() {
+            return implied_tags;
+        }
+        let mut visited_tags = HashSet::new();
+        let mut pending_tags = Self::split_tag_hierarchy(tag.get_tag_name());
+        while let Some(current_tag) = pending_tags.pop() {
+            if !visited_tags.insert(current_tag.clone()) {
+                continue;
+            }
+            let Some(direct_implied_tags) = self.tag_implications.get(&current_tag) else {
+                continue;
+            };
+            for implied_tag in direct_implied_tags.iter() {
+                for hierarchy_tag in Self::split_tag_hierarchy(implied_tag.get_tag_name()) {
+                    if !implied_tags.contains(&hierarchy_tag) {
+                        implied_tags.push(hierarchy_tag.clone());
+                    }
+                    pending_tags.push(hierarchy_tag);
+                }
+            }
+        }
+        implied_tags
+    }
+
+    //按层级拆分标签，A.B.C -> [A, A.B, A.B.C]
+    fn split_tag_hierarchy(full_tag_name: &str) -> Vec<GameplayTag> {
+        let mut hierarchy_tags = Vec::new();
+        for (index, _) in full_tag_name.match_indices('.') {
+            hierarchy_tags.push(GameplayTag::new(&full_tag_name[..index]));
+        }
+        hierarchy_tags.push(GameplayTag::new(full_tag_name));
+        hierarchy_tags
+    }
+
+    //标签在互斥分组下所属的直接子标签名，不在该分组下时返回None
+    fn get_exclusive_option<'a>(group: &GameplayTag, tag: &'a GameplayTag) -> Option<&'a str> {
+        tag.get_tag_name()
+            .strip_prefix(group.get_tag_name())?
+            .strip_prefix('.')?
+            .split('.')
+            .next()
+    }
+
     fn add_tag_node(&mut self, tag_name: String, world: &mut World) {
         let mut current_node_entity = self.root;
         let parts: Vec<&str> = tag_name.split(".").collect();
@@ -109,7 +418,7 @@ impl GameplayTagsManager {
     fn build_complete_tag_container(&self, full_tag_name: &str) -> GameplayTagContainer {
         let mut container = GameplayTagContainer::new();
         let self_tag = GameplayTag::new(full_tag_name);
-        container.gameplay_tags.push(self_tag);
+        container.gameplay_tags.push(self_tag.clone());
         let parts: Vec<&str> = full_tag_name.split('.').collect();
         let mut current_path = String::new();
         for (index, part) in parts.iter().enumerate() {
@@ -128,6 +437,16 @@ impl GameplayTagsManager {
             }
         }
 
+        //隐含标签也作为隐式标签参与匹配
+        for implied_tag in self.collect_implied_tags(&self_tag) {
+            if implied_tag == self_tag {
+                continue;
+            }
+            if let Err(index) = container.parent_tags.binary_search(&implied_tag) {
+                container.parent_tags.insert(index, implied_tag);
+            }
+        }
+
         container
     }
 
@@ -143,6 +462,40 @@ impl GameplayTagsManager {
         }
         None
     }
+
+    ///
+    /// Takes a read-only copy of the registry that can be shared with async compute tasks.
+    /// Cloning the returned snapshot is cheap; taking a new one copies the registry.
+    ///
+    pub fn snapshot(&self) -> GameplayTagsSnapshot {
+        GameplayTagsSnapshot(Arc::new(self.clone()))
+    }
+}
+
+///
+/// Thread-safe, cheaply cloneable snapshot of the `GameplayTagsManager`, taken with
+/// `GameplayTagsManager::snapshot`.
+///
+/// It derefs to `GameplayTagsManager`, so every API taking `&GameplayTagsManager` accepts it.
+/// Tags and rules registered after the snapshot was taken are not visible through it.
+///
+/// # Examples
+/// ```ignore
+/// let tags = tags_manager.snapshot();
+/// AsyncComputeTaskPool::get().spawn(async move {
+///     GameplayTag::new("A.B.C").matches_tag(&GameplayTag::new("A"), &tags)
+/// });
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct GameplayTagsSnapshot(Arc<GameplayTagsManager>);
+
+impl Deref for GameplayTagsSnapshot {
+    type Target = GameplayTagsManager;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
 }
 
 #[derive(Debug, Component)]
@@ -165,6 +518,20 @@ impl GameplayTagNode {
 struct GameplayTagTableRow {
     tag_name: String,
     description: String,
+    #[serde(default)]
+    stacking: Option<GameplayTagStackingPolicy>,
+    //这个标签存在时被阻挡添加的标签
+    #[serde(default)]
+    blocks: Vec<String>,
+    //这个标签存在时被抑制（保留计数但不参与匹配）的标签
+    #[serde(default)]
+    inhibits: Vec<String>,
+    //这个标签的子标签之间互斥
+    #[serde(default)]
+    exclusive: bool,
+    //这个标签在层级之外额外隐含的标签
+    #[serde(default)]
+    implies: Vec<String>,
 }
 
 #[derive(Resource, Debug)]
@@ -207,3 +574,166 @@ impl GameplayTagsSettings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{
+        observer::On,
+        resource::Resource,
+        system::ResMut,
+        world::{DeferredWorld, World},
+    };
+
+    use super::*;
+    use crate::{
+        gameplay_tag_commands::GameplayTagWorldExt,
+        gameplay_tag_container::GameplayTagQuery,
+        gameplay_tag_count_container::{
+            GameplayTagCountContainer, GameplayTagEventType, OnGameplayEffectTagCountChanged,
+        },
+        gameplay_tag_observers::{
+            GameplayTagObserverCommandsExt, GameplayTagObserverRegistry,
+            OnScopedGameplayTagChanged, dispatch_gameplay_tag_observers,
+            register_gameplay_tag_observer,
+        },
+    };
+
+    #[derive(Resource, Default)]
+    struct SeenEvents(Vec<(String, String, bool)>);
+
+    //A.C 隐含 D.C
+    fn setup_world() -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        world.init_resource::<SeenEvents>();
+        assert!(
+            world
+                .resource_mut::<GameplayTagsManager>()
+                .add_tag_implication(GameplayTag::new("A.C"), GameplayTag::new("D.C"))
+        );
+        let entity = world.spawn(GameplayTagCountContainer::new()).id();
+        (world, entity)
+    }
+
+    fn add_tag(world: &mut World, entity: Entity, tag_name: &str) {
+        world
+            .modify_gameplay_tags(entity, |tx| {
+                tx.update_tag_count(&GameplayTag::new(tag_name), 1)
+            })
+            .unwrap();
+        world.flush();
+    }
+
+    #[test]
+    fn implied_tags_are_counted_and_matched_like_parents() {
+        let (mut world, entity) = setup_world();
+        world.add_observer(
+            |trigger: On<OnGameplayEffectTagCountChanged>, mut world: DeferredWorld| {
+                let event = trigger.event();
+                if event.event_type == GameplayTagEventType::NewOrRemoved {
+                    world.resource_mut::<SeenEvents>().0.push((
+                        event.tag.get_tag_name().to_string(),
+                        event.cause_tag.get_tag_name().to_string(),
+                        event.is_explicit,
+                    ));
+                }
+            },
+        );
+        add_tag(&mut world, entity, "A.C");
+
+        let tags_manager = world.resource::<GameplayTagsManager>();
+        let parents = tags_manager.request_gameplay_tag_parents(&GameplayTag::new("A.C"));
+        assert!(parents.has_tag(&GameplayTag::new("D.C")));
+        assert!(parents.has_tag(&GameplayTag::new("D")));
+        assert!(GameplayTag::new("A.C").matches_tag(&GameplayTag::new("D.C"), tags_manager));
+
+        let count_container = world.get::<GameplayTagCountContainer>(entity).unwrap();
+        assert_eq!(count_container.get_tag_count(&GameplayTag::new("D.C")), 1);
+        assert!(count_container.has_matching_gameplay_tag(&GameplayTag::new("D")));
+        let mut implied = GameplayTagContainer::new();
+        implied.gameplay_tags.push(GameplayTag::new("D.C"));
+        let query = GameplayTagQuery::make_query_match_any_tags(&implied);
+        assert!(query.matches(count_container.get_explicit_tags()));
+
+        let seen = &world.resource::<SeenEvents>().0;
+        assert!(seen.contains(&("D.C".to_string(), "A.C".to_string(), false)));
+        assert!(seen.contains(&("A.C".to_string(), "A.C".to_string(), true)));
+    }
+
+    #[test]
+    fn subtree_observer_of_implied_tag_fires_for_implying_tag() {
+        let (mut world, entity) = setup_world();
+        world.init_resource::<GameplayTagObserverRegistry>();
+        world.add_observer(register_gameplay_tag_observer);
+        world.add_observer(dispatch_gameplay_tag_observers);
+        world.commands().observe_tag_subtree(
+            GameplayTag::new("D.C"),
+            GameplayTagEventType::NewOrRemoved,
+            |trigger: On<OnScopedGameplayTagChanged>, mut seen: ResMut<SeenEvents>| {
+                let event = trigger.event();
+                seen.0.push((
+                    event.tag.get_tag_name().to_string(),
+                    event.cause_tag.get_tag_name().to_string(),
+                    event.is_explicit,
+                ));
+            },
+        );
+        world.flush();
+        add_tag(&mut world, entity, "A.C");
+
+        let mut seen = world.resource::<SeenEvents>().0.clone();
+        seen.sort();
+        assert_eq!(
+            seen,
+            vec![
+                ("A.C".to_string(), "A.C".to_string(), true),
+                ("D.C".to_string(), "A.C".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn implying_a_group_member_keeps_its_siblings() {
+        let (mut world, entity) = setup_world();
+        world
+            .resource_mut::<GameplayTagsManager>()
+            .set_exclusive_group(GameplayTag::new("D.C"), true);
+        world
+            .resource_mut::<GameplayTagsManager>()
+            .set_exclusive_group(GameplayTag::new("A"), true);
+        add_tag(&mut world, entity, "D.C.B");
+        add_tag(&mut world, entity, "A.B.C");
+        //A.C 隐含 D.C，但不属于 D.C 分组，所以 D.C.B 保留；A.C 与 A.B.C 同属 A 分组，所以 A.B.C 被替换
+        add_tag(&mut world, entity, "A.C");
+
+        let count_container = world.get::<GameplayTagCountContainer>(entity).unwrap();
+        assert_eq!(count_container.get_tag_count(&GameplayTag::new("D.C.B")), 1);
+        assert_eq!(count_container.get_tag_count(&GameplayTag::new("A.B.C")), 0);
+        assert_eq!(count_container.get_tag_count(&GameplayTag::new("A.C")), 1);
+    }
+
+    #[test]
+    fn snapshot_is_isolated_from_later_registrations() {
+        let mut world = World::new();
+        world.init_resource::<GameplayTagsManager>();
+        let snapshot = world.resource::<GameplayTagsManager>().snapshot();
+        let shared_snapshot = snapshot.clone();
+
+        let mut tags_manager = world.resource_mut::<GameplayTagsManager>();
+        tags_manager.add_tag_implication(GameplayTag::new("A.C"), GameplayTag::new("D.C"));
+        tags_manager.set_stacking_policy(
+            GameplayTag::new("A.C"),
+            GameplayTagStackingPolicy::default(),
+        );
+
+        let (tag, implied) = (GameplayTag::new("A.C"), GameplayTag::new("D.C"));
+        assert!(tag.matches_tag(&implied, &tags_manager));
+        assert!(!tag.matches_tag(&implied, &snapshot));
+        assert!(snapshot.get_stacking_policy(&tag).is_none());
+        let matches_in_task =
+            std::thread::spawn(move || tag.matches_tag(&implied, &shared_snapshot))
+                .join()
+                .unwrap();
+        assert!(!matches_in_task);
+    }
+}
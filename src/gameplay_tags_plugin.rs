@@ -1,5 +1,55 @@
+use crate::gameplay_tag::GameplayTag;
+use crate::gameplay_tag_aggregation::{
+    mark_related_tags_dirty_on_container_removed, mark_related_tags_dirty_on_count_changed,
+    mark_related_tags_dirty_on_relation_replaced, mark_related_tags_dirty_on_view_inserted,
+    update_related_gameplay_tags, DirtyRelatedGameplayTags,
+};
+use crate::gameplay_tag_component_rules::{
+    apply_tag_component_rules, cleanup_tag_component_rules, initialize_tag_component_rules,
+    GameplayTagComponentRule, GameplayTagComponentRules,
+};
+use crate::gameplay_tag_container::GameplayTagQuery;
+use crate::gameplay_tag_count_container::{send_batched_gameplay_tag_changes, GameplayTagsChanged};
+use crate::gameplay_tag_conversion::{
+    apply_gameplay_tag_conversions, forget_gameplay_tag_conversion_state,
+    mark_gameplay_tag_conversion_dirty, GameplayTagConversionRule, GameplayTagConversionRules,
+};
+use crate::gameplay_tag_inheritance::{
+    mark_inherited_tags_dirty_on_container_removed, mark_inherited_tags_dirty_on_count_changed,
+    mark_inherited_tags_dirty_on_parent_removed, mark_inherited_tags_dirty_on_view_inserted,
+    update_inherited_gameplay_tags,
+    DirtyInheritedGameplayTags,
+};
+use crate::gameplay_tag_marker::{
+    sync_tag_marker_on_container_changed, sync_tag_marker_on_container_removed,
+    sync_tag_marker_on_count_changed, sync_tag_marker_on_count_container_removed,
+    GameplayTagMarkerBinding,
+};
+use crate::gameplay_tag_observers::{
+    dispatch_gameplay_tag_observers, register_gameplay_tag_observer,
+    unregister_gameplay_tag_observer, GameplayTagObserverRegistry,
+};
+use crate::gameplay_tag_ops::{apply_gameplay_tag_ops, GameplayTagOpQueue};
+use crate::gameplay_tag_query_registry::{
+    initialize_registered_tag_queries, remove_tag_query_membership, update_tag_query_membership,
+    GameplayTagQueryRegistry,
+};
+use crate::gameplay_tag_sources::remove_gameplay_tag_grantor_contributions;
+use crate::gameplay_tag_stacking::decay_gameplay_tag_stacks;
+use crate::gameplay_tag_states::{
+    update_global_tag_count, update_state_from_global_tags, GameplayTagStateBindings,
+};
+use crate::gameplay_tag_timed_grants::{
+    sync_timed_gameplay_tags_on_removed, tick_timed_gameplay_tags,
+};
 use crate::gameplay_tags_manager::{GameplayTagsManager, GameplayTagsSettings};
-use bevy::app::{App, Plugin};
+use bevy::app::{App, Last, Plugin, PostUpdate, PreUpdate};
+use bevy::ecs::component::Component;
+use bevy::ecs::relationship::Relationship;
+use bevy::ecs::schedule::{IntoScheduleConfigs, common_conditions::resource_exists};
+use bevy::ecs::world::Mut;
+use bevy::state::state::{FreelyMutableState, OnEnter, OnExit, States};
+use bevy::time::Time;
 
 pub struct GameplayTagsPlugin{
     pub data_path: Option<String>,
@@ -9,10 +59,55 @@ impl Plugin for GameplayTagsPlugin {
     fn build(&self, app: &mut App) {
         if let Some(data_path) = &self.data_path {
             app.insert_resource(GameplayTagsSettings::with_data_path(data_path.clone()));
-        }else { 
+        }else {
             app.insert_resource(GameplayTagsSettings::default());
         }
         app.init_resource::<GameplayTagsManager>();
+
+        app.init_resource::<GameplayTagQueryRegistry>()
+            .add_observer(update_tag_query_membership)
+            .add_observer(remove_tag_query_membership)
+            .add_systems(PreUpdate, initialize_registered_tag_queries);
+
+        app.init_resource::<GameplayTagComponentRules>()
+            .add_observer(apply_tag_component_rules)
+            .add_observer(cleanup_tag_component_rules)
+            .add_systems(PreUpdate, initialize_tag_component_rules);
+
+        app.add_observer(remove_gameplay_tag_grantor_contributions);
+
+        app.add_message::<GameplayTagsChanged>()
+            .add_systems(Last, send_batched_gameplay_tag_changes);
+
+        app.init_resource::<GameplayTagObserverRegistry>()
+            .add_observer(register_gameplay_tag_observer)
+            .add_observer(unregister_gameplay_tag_observer)
+            .add_observer(dispatch_gameplay_tag_observers);
+
+        app.init_resource::<GameplayTagConversionRules>()
+            .add_observer(mark_gameplay_tag_conversion_dirty)
+            .add_observer(forget_gameplay_tag_conversion_state)
+            .add_systems(PostUpdate, apply_gameplay_tag_conversions);
+
+        app.init_resource::<DirtyInheritedGameplayTags>()
+            .add_observer(mark_inherited_tags_dirty_on_count_changed)
+            .add_observer(mark_inherited_tags_dirty_on_parent_removed)
+            .add_observer(mark_inherited_tags_dirty_on_container_removed)
+            .add_observer(mark_inherited_tags_dirty_on_view_inserted)
+            .add_systems(PostUpdate, update_inherited_gameplay_tags);
+
+        app.init_resource::<GameplayTagOpQueue>().add_systems(
+            PostUpdate,
+            apply_gameplay_tag_ops
+                .before(apply_gameplay_tag_conversions)
+                .before(update_inherited_gameplay_tags),
+        );
+
+        app.add_observer(sync_timed_gameplay_tags_on_removed).add_systems(
+            PreUpdate,
+            (tick_timed_gameplay_tags, decay_gameplay_tag_stacks)
+                .run_if(resource_exists::<Time>),
+        );
     }
 }
 
@@ -22,10 +117,170 @@ impl GameplayTagsPlugin {
             data_path: None,
         }
     }
-    
+
     pub fn with_data_path(data_path: String) -> Self {
         GameplayTagsPlugin {
             data_path: Some(data_path),
         }
     }
 }
+
+/// Registration helpers for gameplay tag features on `App`.
+pub trait GameplayTagsAppExt {
+    ///
+    /// Registers a named `GameplayTagQuery` whose membership is maintained by the plugin.
+    /// See `GameplayTagQueryRegistry` for details.
+    ///
+    fn register_gameplay_tag_query(
+        &mut self,
+        name: impl Into<String>,
+        query: GameplayTagQuery,
+    ) -> &mut Self;
+
+    ///
+    /// Keeps the marker component `M` inserted on every entity that has `tag` (or a descendant of it).
+    /// Calling this again for the same `M` rebinds it to the new tag.
+    /// See `GameplayTagMarkerBinding` for details.
+    ///
+    fn add_gameplay_tag_marker<M: Component + Default>(&mut self, tag: GameplayTag) -> &mut Self;
+
+    /// Registers a rule that inserts and removes components as entity tags change.
+    fn add_gameplay_tag_component_rule(&mut self, rule: GameplayTagComponentRule) -> &mut Self;
+
+    ///
+    /// Registers a rule that converts tags once their counts reach thresholds.
+    /// Rules fire in registration order, see `GameplayTagConversionRules`.
+    ///
+    fn add_gameplay_tag_conversion_rule(&mut self, rule: GameplayTagConversionRule) -> &mut Self;
+
+    ///
+    /// Keeps `RelatedGameplayTags<R>` up to date on owners of the relationship `R`, i.e. the
+    /// entities holding `R::RelationshipTarget`. Registering the same `R` twice does nothing.
+    ///
+    fn add_related_gameplay_tags<R: Relationship>(&mut self) -> &mut Self;
+
+    ///
+    /// Sets the state `S` to `state` while `tag` is present on the `GlobalGameplayTags` entity.
+    /// `S` must already be initialized with `init_state` / `add_sub_state`.
+    /// See `GameplayTagStateBindings` for how several bindings are resolved.
+    ///
+    fn bind_state_to_gameplay_tag<S: FreelyMutableState>(
+        &mut self,
+        tag: GameplayTag,
+        state: S,
+    ) -> &mut Self;
+
+    /// Sets the state used when none of the tags bound to `S` is present.
+    fn set_gameplay_tag_state_fallback<S: FreelyMutableState>(&mut self, state: S) -> &mut Self;
+
+    ///
+    /// Adds `count` to `tag` on the `GlobalGameplayTags` entity when entering `state`,
+    /// and removes it again when leaving the state.
+    ///
+    fn add_gameplay_tag_on_state<S: States>(
+        &mut self,
+        state: S,
+        tag: GameplayTag,
+        count: i32,
+    ) -> &mut Self;
+}
+
+impl GameplayTagsAppExt for App {
+    fn register_gameplay_tag_query(
+        &mut self,
+        name: impl Into<String>,
+        query: GameplayTagQuery,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<GameplayTagQueryRegistry>()
+            .register(name, query);
+        self
+    }
+
+    fn add_gameplay_tag_marker<M: Component + Default>(&mut self, tag: GameplayTag) -> &mut Self {
+        let already_registered = self
+            .world()
+            .contains_resource::<GameplayTagMarkerBinding<M>>();
+        self.insert_resource(GameplayTagMarkerBinding::<M>::new(tag));
+        if !already_registered {
+            self.add_observer(sync_tag_marker_on_count_changed::<M>)
+                .add_observer(sync_tag_marker_on_container_removed::<M>)
+                .add_observer(sync_tag_marker_on_count_container_removed::<M>)
+                .add_systems(PostUpdate, sync_tag_marker_on_container_changed::<M>);
+        }
+        self
+    }
+
+    fn add_gameplay_tag_component_rule(&mut self, rule: GameplayTagComponentRule) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<GameplayTagComponentRules>()
+            .add_rule(rule);
+        self
+    }
+
+    fn add_gameplay_tag_conversion_rule(&mut self, rule: GameplayTagConversionRule) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<GameplayTagConversionRules>()
+            .add_rule(rule);
+        self
+    }
+
+    fn add_related_gameplay_tags<R: Relationship>(&mut self) -> &mut Self {
+        if self
+            .world()
+            .contains_resource::<DirtyRelatedGameplayTags<R>>()
+        {
+            return self;
+        }
+        self.init_resource::<DirtyRelatedGameplayTags<R>>()
+            .add_observer(mark_related_tags_dirty_on_count_changed::<R>)
+            .add_observer(mark_related_tags_dirty_on_container_removed::<R>)
+            .add_observer(mark_related_tags_dirty_on_relation_replaced::<R>)
+            .add_observer(mark_related_tags_dirty_on_view_inserted::<R>)
+            .add_systems(
+                PostUpdate,
+                update_related_gameplay_tags::<R>.after(apply_gameplay_tag_ops),
+            )
+    }
+
+    fn bind_state_to_gameplay_tag<S: FreelyMutableState>(
+        &mut self,
+        tag: GameplayTag,
+        state: S,
+    ) -> &mut Self {
+        init_gameplay_tag_state_bindings::<S>(self).bind(tag, state);
+        self
+    }
+
+    fn set_gameplay_tag_state_fallback<S: FreelyMutableState>(&mut self, state: S) -> &mut Self {
+        init_gameplay_tag_state_bindings::<S>(self).set_fallback(Some(state));
+        self
+    }
+
+    fn add_gameplay_tag_on_state<S: States>(
+        &mut self,
+        state: S,
+        tag: GameplayTag,
+        count: i32,
+    ) -> &mut Self {
+        self.add_systems(
+            OnEnter(state.clone()),
+            update_global_tag_count(tag.clone(), count),
+        )
+        .add_systems(OnExit(state), update_global_tag_count(tag, -count))
+    }
+}
+
+fn init_gameplay_tag_state_bindings<S: FreelyMutableState>(
+    app: &mut App,
+) -> Mut<'_, GameplayTagStateBindings<S>> {
+    if !app
+        .world()
+        .contains_resource::<GameplayTagStateBindings<S>>()
+    {
+        app.init_resource::<GameplayTagStateBindings<S>>()
+            .add_observer(update_state_from_global_tags::<S>);
+    }
+    app.world_mut()
+        .resource_mut::<GameplayTagStateBindings<S>>()
+}
@@ -1,6 +1,22 @@
 pub mod gameplay_tag;
+pub mod gameplay_tag_aggregation;
+pub mod gameplay_tag_blocking;
+pub mod gameplay_tag_commands;
+pub mod gameplay_tag_component_rules;
+pub mod gameplay_tag_conditions;
 pub mod gameplay_tag_container;
+pub mod gameplay_tag_conversion;
 pub mod gameplay_tag_count_container;
+pub mod gameplay_tag_inheritance;
+pub mod gameplay_tag_marker;
+pub mod gameplay_tag_observers;
+pub mod gameplay_tag_ops;
+pub mod gameplay_tag_query_registry;
+pub mod gameplay_tag_relationship_mapping;
 pub mod gameplay_tag_requirements;
+pub mod gameplay_tag_sources;
+pub mod gameplay_tag_stacking;
+pub mod gameplay_tag_states;
+pub mod gameplay_tag_timed_grants;
 pub mod gameplay_tags_manager;
 pub mod gameplay_tags_plugin;